@@ -0,0 +1,183 @@
+//! Compares the flat `TileMap` storage with the previous `Vec<Vec<Tile>>` layout.
+//!
+//! Run with `cargo bench -p board-plugin`, requires a nightly compiler.
+#![feature(test)]
+
+extern crate test;
+
+use board_plugin::components::Coordinates;
+use board_plugin::resources::{Tile, TileMap};
+use rand::Rng;
+use test::{black_box, Bencher};
+
+const NEIGHBORS: [(i8, i8); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Previous layout, one allocation per row, with the previous accessors
+struct NestedTileMap {
+    width: u16,
+    height: u16,
+    map: Vec<Vec<Tile>>,
+}
+
+impl NestedTileMap {
+    fn new(tile_map: &TileMap) -> Self {
+        Self {
+            width: tile_map.width,
+            height: tile_map.height,
+            map: tile_map.rows().map(<[Tile]>::to_vec).collect(),
+        }
+    }
+
+    fn is_mine_at(&self, coordinates: Coordinates) -> bool {
+        if coordinates.x >= self.width || coordinates.y >= self.height {
+            return false;
+        }
+        self.map[coordinates.y as usize][coordinates.x as usize] == Tile::Mine
+    }
+
+    fn neighbor_counts(&self) -> usize {
+        let mut total = 0;
+        for (y, line) in self.map.iter().enumerate() {
+            for x in 0..line.len() {
+                let coordinates = Coordinates {
+                    x: x as u16,
+                    y: y as u16,
+                };
+                total += NEIGHBORS
+                    .iter()
+                    .filter(|offset| self.is_mine_at(coordinates + **offset))
+                    .count();
+            }
+        }
+        total
+    }
+
+    fn count_mines(&self) -> usize {
+        self.map
+            .iter()
+            .flat_map(|line| line.iter())
+            .filter(|tile| **tile == Tile::Mine)
+            .count()
+    }
+}
+
+fn tile_map(size: u32) -> TileMap {
+    // 20% of the tiles like an expert board, but the mine count is a `u16`:
+    // 2000x2000 boards are capped at 65535 mines, about 1.6% of their tiles
+    let mine_count = (size * size / 5).min(u16::MAX as u32) as u16;
+    let mut tile_map = TileMap::empty(size as u16, size as u16);
+    tile_map.place_mines_seeded(mine_count, 42);
+    tile_map
+}
+
+/// As many coordinates as there are tiles, in random order,
+/// so random access reads as many tiles as the iteration benches
+fn random_coordinates(size: u32) -> Vec<Coordinates> {
+    let mut rng = TileMap::seeded_rng(42);
+    (0..size * size)
+        .map(|_| Coordinates {
+            x: rng.gen_range(0..size) as u16,
+            y: rng.gen_range(0..size) as u16,
+        })
+        .collect()
+}
+
+fn flat_neighbor_counts(tile_map: &TileMap) -> usize {
+    tile_map
+        .iter_with_coordinates()
+        .map(|(coordinates, _)| {
+            NEIGHBORS
+                .iter()
+                .filter(|offset| tile_map.is_mine_at(coordinates + **offset))
+                .count()
+        })
+        .sum()
+}
+
+fn flat_count_mines(tile_map: &TileMap) -> usize {
+    tile_map
+        .iter_with_coordinates()
+        .filter(|(_, tile)| **tile == Tile::Mine)
+        .count()
+}
+
+macro_rules! bench_size {
+    ($module:ident, $size:expr) => {
+        mod $module {
+            use super::*;
+
+            #[bench]
+            fn flat_neighbor_counts(b: &mut Bencher) {
+                let tile_map = tile_map($size);
+                b.iter(|| super::flat_neighbor_counts(black_box(&tile_map)));
+            }
+
+            #[bench]
+            fn nested_neighbor_counts(b: &mut Bencher) {
+                let tile_map = NestedTileMap::new(&tile_map($size));
+                b.iter(|| black_box(&tile_map).neighbor_counts());
+            }
+
+            #[bench]
+            fn flat_iteration(b: &mut Bencher) {
+                let tile_map = tile_map($size);
+                b.iter(|| flat_count_mines(black_box(&tile_map)));
+            }
+
+            #[bench]
+            fn nested_iteration(b: &mut Bencher) {
+                let tile_map = NestedTileMap::new(&tile_map($size));
+                b.iter(|| black_box(&tile_map).count_mines());
+            }
+
+            #[bench]
+            fn flat_clone(b: &mut Bencher) {
+                let tile_map = tile_map($size);
+                b.iter(|| black_box(&tile_map).clone());
+            }
+
+            #[bench]
+            fn nested_clone(b: &mut Bencher) {
+                let tile_map = NestedTileMap::new(&tile_map($size));
+                b.iter(|| black_box(&tile_map).map.clone());
+            }
+
+            #[bench]
+            fn flat_random_access(b: &mut Bencher) {
+                let tile_map = tile_map($size);
+                let coordinates = random_coordinates($size);
+                b.iter(|| {
+                    coordinates
+                        .iter()
+                        .filter(|coordinates| black_box(&tile_map).is_mine_at(**coordinates))
+                        .count()
+                });
+            }
+
+            #[bench]
+            fn nested_random_access(b: &mut Bencher) {
+                let tile_map = NestedTileMap::new(&tile_map($size));
+                let coordinates = random_coordinates($size);
+                b.iter(|| {
+                    coordinates
+                        .iter()
+                        .filter(|coordinates| black_box(&tile_map).is_mine_at(**coordinates))
+                        .count()
+                });
+            }
+        }
+    };
+}
+
+bench_size!(size_100, 100u32);
+bench_size!(size_500, 500u32);
+bench_size!(size_2000, 2000u32);
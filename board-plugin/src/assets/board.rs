@@ -12,6 +12,14 @@ pub struct BoardAssets {
     pub covered_tile_material: SpriteMaterial,
     pub mine_material: SpriteMaterial,
     pub flag_material: SpriteMaterial,
+    /// Drawn over question marked tiles, see `BoardOptions::question_marks`
+    pub question_material: SpriteMaterial,
+    /// Replaces the tile material of the mine that exploded
+    pub exploded_mine_material: SpriteMaterial,
+    /// Replaces the flag material of tiles wrongly marked as mines once the game is lost
+    pub wrong_flag_material: SpriteMaterial,
+    /// Drawn over the tile under the keyboard cursor, should be translucent
+    pub cursor_material: SpriteMaterial,
     /// Used for the tiles neighboring mines
     pub neighbor_font: Handle<Font>,
     /// First one will be used for neighbors of a single mine, second color for neighbors of two mines, etc.
@@ -2,6 +2,8 @@ use bevy::prelude::*;
 use bevy::render::texture::DEFAULT_IMAGE_HANDLE;
 
 pub mod board;
+pub mod puzzle;
+pub mod tile_atlas;
 
 /// Material of a `Sprite` with a texture and color
 #[derive(Debug, Clone)]
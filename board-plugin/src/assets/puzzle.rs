@@ -0,0 +1,197 @@
+use crate::components::Coordinates;
+use crate::resources::{BoardSnapshot, BoardStatus, FormatError, TileMap};
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// Hand-authored board, loaded from `.puzzle` files.
+///
+/// Puzzle files are RON structs with a `layout` in the text grid format of `TileMap::from_text`,
+/// optional `revealed` and `flagged` tile coordinates, counted from the bottom left,
+/// and optional `title` and `author` metadata:
+/// ```ron
+/// (
+///     title: "Corner",
+///     author: Some("Jan"),
+///     layout: r#"
+/// *..
+/// ...
+/// "#,
+///     revealed: [(x: 2, y: 0)],
+///     flagged: [(x: 0, y: 1)],
+/// )
+/// ```
+#[derive(Debug, Clone, TypeUuid)]
+#[uuid = "6c4d7b3e-0f0c-4f1b-9d8a-2f4f3c1c2b6e"]
+pub struct Puzzle {
+    pub title: String,
+    pub author: Option<String>,
+    pub tile_map: TileMap,
+    /// Tiles uncovered when the board is created
+    pub revealed: Vec<Coordinates>,
+    /// Tiles marked when the board is created
+    pub flagged: Vec<Coordinates>,
+}
+
+/// Puzzle file contents
+#[derive(Debug, Deserialize)]
+struct PuzzleFile {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    author: Option<String>,
+    layout: String,
+    #[serde(default)]
+    revealed: Vec<Coordinates>,
+    #[serde(default)]
+    flagged: Vec<Coordinates>,
+}
+
+/// Error raised when a puzzle file is invalid
+#[derive(Debug)]
+pub enum PuzzleError {
+    Ron(ron::Error),
+    Layout(FormatError),
+    /// Revealed tiles must be safe tiles of the board
+    InvalidRevealedTile(Coordinates),
+    /// Flagged tiles must be covered tiles of the board
+    InvalidFlaggedTile(Coordinates),
+}
+
+impl Display for PuzzleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ron(error) => write!(f, "Invalid puzzle: {}", error),
+            Self::Layout(error) => write!(f, "Invalid puzzle layout: {}", error),
+            Self::InvalidRevealedTile(coordinates) => {
+                write!(f, "Puzzle reveals {} which is not a safe tile", coordinates)
+            }
+            Self::InvalidFlaggedTile(coordinates) => {
+                write!(
+                    f,
+                    "Puzzle flags {} which is not a covered tile",
+                    coordinates
+                )
+            }
+        }
+    }
+}
+
+impl Error for PuzzleError {}
+
+impl From<ron::Error> for PuzzleError {
+    fn from(error: ron::Error) -> Self {
+        Self::Ron(error)
+    }
+}
+
+impl From<FormatError> for PuzzleError {
+    fn from(error: FormatError) -> Self {
+        Self::Layout(error)
+    }
+}
+
+impl Puzzle {
+    pub fn from_ron(ron: &str) -> Result<Self, PuzzleError> {
+        let file: PuzzleFile = ron::from_str(ron)?;
+        let tile_map = TileMap::from_text(&file.layout)?;
+        let is_on_board = |coordinates: &Coordinates| {
+            coordinates.x < tile_map.width && coordinates.y < tile_map.height
+        };
+        if let Some(coordinates) = file
+            .revealed
+            .iter()
+            .find(|coordinates| !is_on_board(coordinates) || tile_map.is_mine_at(**coordinates))
+        {
+            return Err(PuzzleError::InvalidRevealedTile(*coordinates));
+        }
+        if let Some(coordinates) = file
+            .flagged
+            .iter()
+            .find(|coordinates| !is_on_board(coordinates) || file.revealed.contains(coordinates))
+        {
+            return Err(PuzzleError::InvalidFlaggedTile(*coordinates));
+        }
+        Ok(Self {
+            title: file.title,
+            author: file.author,
+            tile_map,
+            revealed: file.revealed,
+            flagged: file.flagged,
+        })
+    }
+
+    /// State of the board at the start of the puzzle
+    pub fn snapshot(&self) -> BoardSnapshot {
+        let covered_tiles = (0..self.tile_map.height)
+            .flat_map(|y| (0..self.tile_map.width).map(move |x| Coordinates { x, y }))
+            .filter(|coordinates| !self.revealed.contains(coordinates))
+            .collect();
+        BoardSnapshot {
+            tile_map: self.tile_map.clone(),
+            covered_tiles,
+            marked_tiles: self.flagged.clone(),
+            questioned_tiles: Vec::new(),
+            uncovering_tiles: Vec::new(),
+            status: BoardStatus::Playing,
+            started: false,
+            stats: Default::default(),
+            seed: 0,
+            deferred_placement: None,
+        }
+    }
+}
+
+/// Loader of `.puzzle` files, registered by the board plugin
+#[derive(Debug, Default)]
+pub struct PuzzleLoader;
+
+impl AssetLoader for PuzzleLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let puzzle = Puzzle::from_ron(std::str::from_utf8(bytes)?)?;
+            load_context.set_default_asset(LoadedAsset::new(puzzle));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["puzzle"]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reads_puzzle_file() {
+        let puzzle = Puzzle::from_ron(
+            r##"(
+    title: "Corner",
+    layout: r#"
+*..
+...
+"#,
+    revealed: [(x: 2, y: 0)],
+    flagged: [(x: 0, y: 1)],
+)"##,
+        )
+        .unwrap();
+        assert_eq!(puzzle.title, "Corner");
+        assert_eq!(puzzle.author, None);
+        assert!(puzzle.tile_map.is_mine_at(Coordinates { x: 0, y: 1 }));
+        let snapshot = puzzle.snapshot();
+        assert_eq!(snapshot.covered_tiles.len(), 5);
+        assert_eq!(snapshot.marked_tiles, vec![Coordinates { x: 0, y: 1 }]);
+
+        let error = Puzzle::from_ron(r#"(layout: "*.", revealed: [(x: 0, y: 0)])"#).unwrap_err();
+        assert!(matches!(error, PuzzleError::InvalidRevealedTile(_)));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::assets::{board::BoardAssets, SpriteMaterial};
+use bevy::math::Vec2;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, FilterMode, TextureDimension, TextureFormat};
+
+/// Pixel size of an atlas cell
+const CELL_SIZE: usize = 16;
+/// Cells in the atlas, a single row
+const CELL_COUNT: usize = 16;
+
+/// 3x5 pixel digits drawn on neighbor tiles, one bit per pixel from the top row
+const DIGITS: [[u8; 5]; 8] = [
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b110, 0b001, 0b010, 0b100, 0b111],
+    [0b110, 0b001, 0b010, 0b001, 0b110],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b110, 0b001, 0b110],
+    [0b011, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+];
+
+/// Look of a tile on a chunked board
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TileFace {
+    Covered,
+    Flagged,
+    /// Flag on a tile without mine, once the game is lost
+    WrongFlag,
+    Empty,
+    Neighbor(u8),
+    /// Mine revealed once the game is lost
+    Mine,
+    ExplodedMine,
+    Questioned,
+}
+
+impl TileFace {
+    fn cell(&self) -> usize {
+        match *self {
+            Self::Covered => 0,
+            Self::Flagged => 1,
+            Self::WrongFlag => 2,
+            Self::Empty => 3,
+            Self::Neighbor(count) => 3 + count.clamp(1, 8) as usize,
+            Self::Mine => 12,
+            Self::ExplodedMine => 13,
+            Self::Questioned => 14,
+        }
+    }
+
+    /// Texture coordinates of the face in the atlas, top left and bottom right
+    pub fn uv_rect(&self) -> (Vec2, Vec2) {
+        let width = (CELL_SIZE * CELL_COUNT) as f32;
+        let cell = self.cell() as f32;
+        // Half a pixel inset keeps neighboring cells from bleeding in
+        let inset = 0.5;
+        (
+            Vec2::new(
+                (cell * CELL_SIZE as f32 + inset) / width,
+                inset / CELL_SIZE as f32,
+            ),
+            Vec2::new(
+                ((cell + 1.) * CELL_SIZE as f32 - inset) / width,
+                1. - inset / CELL_SIZE as f32,
+            ),
+        )
+    }
+}
+
+/// Texture with every `TileFace` drawn from the board assets, shared by the chunks of a board
+#[derive(Debug, Clone)]
+pub struct TileAtlas {
+    pub image: Handle<Image>,
+    pub material: Handle<ColorMaterial>,
+}
+
+impl TileAtlas {
+    /// Textures the atlas is drawn from, it must be redrawn when they change
+    pub fn sources(board_assets: &BoardAssets) -> [&Handle<Image>; 7] {
+        [
+            &board_assets.covered_tile_material.texture,
+            &board_assets.tile_material.texture,
+            &board_assets.flag_material.texture,
+            &board_assets.question_material.texture,
+            &board_assets.wrong_flag_material.texture,
+            &board_assets.mine_material.texture,
+            &board_assets.exploded_mine_material.texture,
+        ]
+    }
+
+    /// Draws the atlas image. Textures that are not loaded yet are skipped.
+    pub fn draw(board_assets: &BoardAssets, images: &Assets<Image>) -> Image {
+        let mut image = Image::new(
+            Extent3d {
+                width: (CELL_SIZE * CELL_COUNT) as u32,
+                height: CELL_SIZE as u32,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            vec![0; CELL_SIZE * CELL_COUNT * CELL_SIZE * 4],
+            TextureFormat::Rgba8UnormSrgb,
+        );
+        image.sampler_descriptor.mag_filter = FilterMode::Nearest;
+        image.sampler_descriptor.min_filter = FilterMode::Nearest;
+
+        let mut blit = |face: TileFace, material: &SpriteMaterial| {
+            if let Some(texture) = images.get(&material.texture) {
+                blit_cell(&mut image, face.cell(), texture, material.color);
+            }
+        };
+        blit(TileFace::Covered, &board_assets.covered_tile_material);
+        blit(TileFace::Flagged, &board_assets.covered_tile_material);
+        blit(TileFace::Flagged, &board_assets.flag_material);
+        blit(TileFace::WrongFlag, &board_assets.covered_tile_material);
+        blit(TileFace::WrongFlag, &board_assets.wrong_flag_material);
+        blit(TileFace::Empty, &board_assets.tile_material);
+        for count in 1..=8 {
+            blit(TileFace::Neighbor(count), &board_assets.tile_material);
+        }
+        blit(TileFace::Mine, &board_assets.tile_material);
+        blit(TileFace::Mine, &board_assets.mine_material);
+        blit(TileFace::ExplodedMine, &board_assets.exploded_mine_material);
+        blit(TileFace::ExplodedMine, &board_assets.mine_material);
+        blit(TileFace::Questioned, &board_assets.covered_tile_material);
+        blit(TileFace::Questioned, &board_assets.question_material);
+
+        for count in 1..=8 {
+            let color = board_assets.get_mine_counter_color(count);
+            draw_digit(&mut image, TileFace::Neighbor(count).cell(), count, color);
+        }
+        image
+    }
+}
+
+/// Blends `color` over the atlas pixel at `(x, y)`
+fn blend_pixel(atlas: &mut Image, x: usize, y: usize, color: [f32; 4]) {
+    let index = (y * CELL_SIZE * CELL_COUNT + x) * 4;
+    let pixel = &mut atlas.data[index..index + 4];
+    let alpha = color[3];
+    for channel in 0..3 {
+        let blended = color[channel] * alpha + (pixel[channel] as f32 / 255.) * (1. - alpha);
+        pixel[channel] = (blended.clamp(0., 1.) * 255.).round() as u8;
+    }
+    let blended_alpha = alpha + (pixel[3] as f32 / 255.) * (1. - alpha);
+    pixel[3] = (blended_alpha.clamp(0., 1.) * 255.).round() as u8;
+}
+
+/// Draws a tinted texture over a whole cell, sampling the nearest texture pixel
+fn blit_cell(atlas: &mut Image, cell: usize, texture: &Image, tint: Color) {
+    if texture.texture_descriptor.format != TextureFormat::Rgba8UnormSrgb {
+        return;
+    }
+    let size = texture.texture_descriptor.size;
+    let (width, height) = (size.width as usize, size.height as usize);
+    if width == 0 || height == 0 || texture.data.len() < width * height * 4 {
+        return;
+    }
+    let tint = tint.as_rgba_f32();
+    for y in 0..CELL_SIZE {
+        for x in 0..CELL_SIZE {
+            let index = ((y * height / CELL_SIZE) * width + x * width / CELL_SIZE) * 4;
+            let source = &texture.data[index..index + 4];
+            let mut color = [0.; 4];
+            for channel in 0..4 {
+                color[channel] = source[channel] as f32 / 255. * tint[channel];
+            }
+            blend_pixel(atlas, cell * CELL_SIZE + x, y, color);
+        }
+    }
+}
+
+/// Draws a digit, scaled up twice, in the middle of a cell
+fn draw_digit(atlas: &mut Image, cell: usize, digit: u8, color: Color) {
+    let rows = DIGITS[(digit.clamp(1, 8) - 1) as usize];
+    let (left, top) = (cell * CELL_SIZE + (CELL_SIZE - 6) / 2, (CELL_SIZE - 10) / 2);
+    let color = color.as_rgba_f32();
+    for (row, bits) in rows.iter().enumerate() {
+        for column in 0..3 {
+            if bits & (0b100 >> column) == 0 {
+                continue;
+            }
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                blend_pixel(atlas, left + column * 2 + dx, top + row * 2 + dy, color);
+            }
+        }
+    }
+}
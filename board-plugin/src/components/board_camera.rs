@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+/// Marks the 2D camera looking at the board.
+/// Tiles are picked through this camera, which is panned and zoomed according to the `CameraOptions`.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash, Component)]
+pub struct BoardCamera;
+
+impl BoardCamera {
+    /// World space ray going through a window position, as its origin and direction.
+    /// Window positions start at the bottom left and are in logical pixels like `Window::cursor_position`,
+    /// so they do not depend on the HiDPI scale factor.
+    pub fn screen_to_ray(
+        screen_position: Vec2,
+        window_size: Vec2,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+    ) -> (Vec3, Vec3) {
+        // Window to normalized device coordinates, from -1 to 1 on both axes
+        let ndc = screen_position / window_size * 2. - Vec2::ONE;
+        let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix.inverse();
+        // Projections use reversed depth, from 1 at the near plane to 0 at the far plane
+        let near = ndc_to_world.project_point3(ndc.extend(1.));
+        let far = ndc_to_world.project_point3(ndc.extend(0.));
+        (near, far - near)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy::render::camera::CameraProjection;
+
+    #[test]
+    fn screen_to_ray_follows_camera() {
+        let window_size = Vec2::new(800., 600.);
+        let mut projection = OrthographicProjection {
+            scale: 2.,
+            ..Default::default()
+        };
+        projection.update(window_size.x, window_size.y);
+        let camera = Camera {
+            projection_matrix: projection.get_projection_matrix(),
+            ..Default::default()
+        };
+        let transform = GlobalTransform::from_xyz(100., 50., 999.);
+
+        let (center, direction) =
+            BoardCamera::screen_to_ray(window_size / 2., window_size, &camera, &transform);
+        assert!(center.truncate().abs_diff_eq(Vec2::new(100., 50.), 1e-3));
+        assert!(direction.normalize().abs_diff_eq(-Vec3::Z, 1e-3));
+        let (corner, _) = BoardCamera::screen_to_ray(window_size, window_size, &camera, &transform);
+        assert!(corner.truncate().abs_diff_eq(Vec2::new(900., 650.), 1e-3));
+    }
+}
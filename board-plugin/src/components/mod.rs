@@ -1,9 +1,13 @@
+pub use board_camera::*;
 pub use coordinates::Coordinates;
 pub use mine::*;
 pub use neighbor::*;
-pub use uncovered::*;
+pub use tile_chunk::*;
+pub use tile_cursor::*;
 
+mod board_camera;
 mod coordinates;
 mod mine;
 mod neighbor;
-mod uncovered;
+mod tile_chunk;
+mod tile_cursor;
@@ -0,0 +1,32 @@
+use crate::components::Coordinates;
+use bevy::prelude::*;
+
+/// Square of tiles drawn as a single mesh on chunked boards
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Component)]
+pub struct TileChunk {
+    /// Bottom left tile of the chunk
+    pub origin: Coordinates,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl TileChunk {
+    pub fn contains(&self, coordinates: Coordinates) -> bool {
+        coordinates.x >= self.origin.x
+            && coordinates.y >= self.origin.y
+            && coordinates.x - self.origin.x < self.width
+            && coordinates.y - self.origin.y < self.height
+    }
+
+    /// Tile coordinates of the chunk, row by row from the bottom left
+    pub fn coordinates(&self) -> impl Iterator<Item = Coordinates> {
+        let origin = self.origin;
+        let width = self.width;
+        (0..self.height).flat_map(move |y| {
+            (0..width).map(move |x| Coordinates {
+                x: origin.x + x,
+                y: origin.y + y,
+            })
+        })
+    }
+}
@@ -0,0 +1,55 @@
+use crate::components::Coordinates;
+use bevy::prelude::*;
+
+/// Cursor highlighting a tile of the board, moved with the keyboard or a gamepad.
+/// Hidden until first used, keyboard cursors are hidden again once the mouse is used.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash, Component)]
+pub struct TileCursor {
+    pub coordinates: Coordinates,
+    /// Gamepad moving the cursor, the keyboard moves the cursor without gamepad
+    pub gamepad: Option<Gamepad>,
+}
+
+impl TileCursor {
+    /// Coordinates one step in `direction` on a board of the given size,
+    /// either wrapping around or stopping at the edges
+    pub fn step(&self, (dx, dy): (i8, i8), (width, height): (u16, u16), wrap: bool) -> Coordinates {
+        let step = |value: u16, delta: i8, size: u16| {
+            let value = value as i32 + delta as i32;
+            if wrap {
+                value.rem_euclid(size.max(1) as i32) as u16
+            } else {
+                value.clamp(0, size.saturating_sub(1) as i32) as u16
+            }
+        };
+        Coordinates {
+            x: step(self.coordinates.x, dx, width),
+            y: step(self.coordinates.y, dy, height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn steps_with_wrap_or_clamp() {
+        let cursor = TileCursor {
+            coordinates: Coordinates { x: 0, y: 2 },
+            gamepad: None,
+        };
+        assert_eq!(
+            cursor.step((-1, 1), (4, 3), false),
+            Coordinates { x: 0, y: 2 }
+        );
+        assert_eq!(
+            cursor.step((-1, 1), (4, 3), true),
+            Coordinates { x: 3, y: 0 }
+        );
+        assert_eq!(
+            cursor.step((1, -1), (4, 3), false),
+            Coordinates { x: 1, y: 1 }
+        );
+    }
+}
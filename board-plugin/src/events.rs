@@ -1,12 +1,115 @@
 use crate::components::Coordinates;
+use crate::resources::{GenerationError, ReplayAction, Tile};
+use std::time::Duration;
+
+/// Player action on the board, passed on as its tile, undo, redo or hint event.
+/// Input systems send these rather than the events of each action, so the `ReplayRecorder` sees them in order.
+#[derive(Debug, Clone, Copy)]
+pub struct BoardActionEvent(pub ReplayAction);
+
+/// Uncovers the tile at the given coordinates
 #[derive(Debug, Clone, Copy)]
 pub struct TileTriggerEvent(pub Coordinates);
 
+/// Toggles the mark of the tile at the given coordinates
 #[derive(Debug, Clone, Copy)]
 pub struct TileMarkEvent(pub Coordinates);
 
+/// Uncovers the covered neighbors of an uncovered tile once all of its neighboring mines are marked
+#[derive(Debug, Clone, Copy)]
+pub struct TileChordEvent(pub Coordinates);
+
+/// Sent once when every tile without a mine is uncovered
+#[derive(Debug, Clone, Copy)]
+pub struct BoardCompletedEvent {
+    /// Time played, see `BoardStats::elapsed`
+    pub elapsed: Duration,
+    pub clicks: u32,
+    pub seed: u64,
+}
+
+/// Sent once when the first mine is uncovered
+#[derive(Debug, Clone, Copy)]
+pub struct MineExplodedEvent {
+    pub coordinates: Coordinates,
+    /// Time played, see `BoardStats::elapsed`
+    pub elapsed: Duration,
+    pub clicks: u32,
+    pub seed: u64,
+}
+
+/// Sent for every tile uncovered, including tiles uncovered by propagation
+#[derive(Debug, Clone, Copy)]
+pub struct TileUncoveredEvent {
+    pub coordinates: Coordinates,
+    pub tile: Tile,
+}
+
+/// Sent when a tile is marked or unmarked
+#[derive(Debug, Clone, Copy)]
+pub struct TileFlaggedEvent {
+    pub coordinates: Coordinates,
+    /// New mark state
+    pub flagged: bool,
+    /// New question mark state
+    pub questioned: bool,
+}
+
+/// Sent whenever the game timer reaches a new whole second, and once the `BoardStats` are inserted
+#[derive(Debug, Clone, Copy)]
+pub struct TimerTickedEvent {
+    /// Time played, see `BoardStats::elapsed`
+    pub elapsed: Duration,
+}
+
+/// Sent once the mines of a board are placed,
+/// which happens on the first uncovered tile when mine placement is deferred
+#[derive(Debug, Clone, Copy)]
+pub struct BoardGeneratedEvent {
+    pub width: u16,
+    pub height: u16,
+    pub mine_count: u16,
+    pub seed: u64,
+}
+
+/// Sent when the generator found no acceptable mine layout, the board is played with the last attempted one,
+/// or when the generator cannot be used with the `BoardOptions`, the mines are placed at random then.
+/// Lowering the mine density makes a layout more likely to be found.
+#[derive(Debug, Clone, Copy)]
+pub struct GenerationFailedEvent {
+    pub error: GenerationError,
+    pub seed: u64,
+}
+
+/// Undoes the last move of the `BoardHistory`
+#[derive(Debug, Clone, Copy)]
+pub struct UndoEvent;
+
+/// Plays the last undone move of the `BoardHistory` again
+#[derive(Debug, Clone, Copy)]
+pub struct RedoEvent;
+
+/// Asks the solver for a tile to play, see `TileHintedEvent`
+#[derive(Debug, Clone, Copy)]
+pub struct HintEvent;
+
+/// Sent for every hint given, the keyboard tile cursor is moved onto the hinted tile.
+/// Provably safe tiles are uncovered right away.
+#[derive(Debug, Clone, Copy)]
+pub struct TileHintedEvent {
+    pub coordinates: Coordinates,
+    /// Estimated chance of the tile being a mine, 0 if it is provably safe
+    pub risk: f32,
+}
+
+/// Sent for every tile covered again by an undo
 #[derive(Debug, Clone, Copy)]
-pub struct BoardCompletedEvent;
+pub struct TileCoveredEvent {
+    pub coordinates: Coordinates,
+}
 
+/// Sent when the move that made a mine explode is undone, the game goes on
 #[derive(Debug, Clone, Copy)]
-pub struct MineExplodedEvent;
+pub struct ExplosionUndoneEvent {
+    pub coordinates: Coordinates,
+}
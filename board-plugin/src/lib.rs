@@ -3,18 +3,38 @@
 pub mod assets;
 mod bounds;
 pub mod components;
-mod events;
+pub mod events;
 pub mod resources;
+mod solver;
 mod systems;
 
-use bevy::ecs::schedule::StateData;
+use assets::puzzle::{Puzzle, PuzzleLoader};
+use assets::tile_atlas::TileAtlas;
+use bevy::ecs::schedule::{RunCriteriaLabel, ShouldRun, StateData, SystemLabel};
 use bevy::log;
 use bevy::prelude::*;
 #[cfg(feature = "debug")]
 use bevy_inspector_egui::RegisterInspectable;
 #[cfg(feature = "debug")]
 use components::*;
-use resources::Board;
+use resources::{
+    Board, BoardHistory, BoardStats, CameraOptions, CursorOptions, GamepadOptions, InputMap,
+    TouchOptions,
+};
+
+/// State run criteria shared by the board system sets
+#[derive(Debug, Clone, PartialEq, Eq, Hash, RunCriteriaLabel)]
+enum BoardRunCriteria {
+    Update,
+    InStackUpdate,
+}
+
+/// Labels ordering the board systems
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+enum BoardSystem {
+    /// Passes the `BoardActionEvent`s on to the systems handling each action, in the same frame
+    DispatchActions,
+}
 
 pub struct BoardPlugin<T> {
     pub current_state: T,
@@ -25,43 +45,128 @@ impl<T: StateData> Plugin for BoardPlugin<T> {
         let initial_state = self.current_state.clone();
         app.add_system_set(
             // Active when the initial state is pushed onto the stack
-            SystemSet::on_enter(initial_state.clone()).with_system(systems::startup::create_board),
+            SystemSet::on_enter(initial_state.clone()).with_system(systems::replay::reset_recorder),
+        )
+        .add_system_set(
+            // Creates the board once it can be built, i.e. once its puzzle is loaded
+            SystemSet::new()
+                .with_run_criteria(
+                    State::<T>::on_in_stack_update(initial_state.clone())
+                        .label(BoardRunCriteria::InStackUpdate),
+                )
+                .with_system(systems::startup::create_board),
         )
+        .stage(CoreStage::Update, |stage: &mut SystemStage| {
+            // State criteria piped by the board systems below, without systems of its own
+            stage.add_system_run_criteria(
+                State::<T>::on_update(initial_state.clone()).label(BoardRunCriteria::Update),
+            )
+        })
         .add_system_set(
-            // Active when the initial state active, i.e. on top of the stack
-            SystemSet::on_update(initial_state.clone())
+            // Active when the initial state active, i.e. on top of the stack, and the board created
+            SystemSet::new()
+                .with_run_criteria(BoardRunCriteria::Update.pipe(board_created.system()))
+                .with_system(systems::action::dispatch_actions.label(BoardSystem::DispatchActions))
                 .with_system(systems::input_handling)
-                .with_system(systems::uncover::trigger_event_handler),
+                .with_system(systems::cursor::keyboard_input)
+                .with_system(systems::gamepad::gamepad_input)
+                .with_system(systems::touch::touch_input)
+                .with_system(systems::history::history_input)
+                .with_system(systems::history::undo_moves.after(BoardSystem::DispatchActions))
+                .with_system(systems::hint::hint_input)
+                .with_system(systems::hint::give_hints.after(BoardSystem::DispatchActions))
+                .with_system(
+                    systems::placement::deferred_mine_placement.after(BoardSystem::DispatchActions),
+                )
+                .with_system(
+                    systems::uncover::trigger_event_handler.after(BoardSystem::DispatchActions),
+                )
+                .with_system(
+                    systems::uncover::chord_event_handler.after(BoardSystem::DispatchActions),
+                )
+                .with_system(systems::stats::tick_timer)
+                .with_system(systems::replay::record_actions)
+                .with_system(systems::replay::play_actions)
+                .with_system(systems::camera::drag_camera)
+                .with_system(systems::camera::scroll_camera)
+                .with_system(systems::camera::zoom_camera)
+                .with_system(systems::camera::fit_camera)
+                .with_system(systems::camera::follow_tile_cursor),
         )
         .add_system_set(
-            // Active when the initial state is in the stack, no matter where
-            SystemSet::on_in_stack_update(initial_state.clone())
+            // Active when the initial state is in the stack, no matter where, and the board created
+            SystemSet::new()
+                .with_run_criteria(BoardRunCriteria::InStackUpdate.pipe(board_created.system()))
                 .with_system(systems::uncover::uncover_tiles)
-                .with_system(systems::mark::mark_tiles),
+                .with_system(systems::mark::mark_tiles.after(BoardSystem::DispatchActions))
+                .with_system(systems::reveal::reveal_board)
+                .with_system(systems::reveal::flag_remaining_mines)
+                .with_system(systems::reveal::conceal_board)
+                .with_system(systems::puzzle::reload_puzzle)
+                .with_system(systems::chunks::build_tile_chunks)
+                .with_system(systems::chunks::update_tile_chunks)
+                .with_system(systems::chunks::update_tile_atlas)
+                .with_system(systems::layout::relayout_board)
+                .with_system(systems::cursor::place_tile_cursor)
+                .with_system(systems::cursor::hide_tile_cursor)
+                .with_system(systems::gamepad::sync_gamepad_cursors)
+                .with_system(systems::stats::count_remaining_mines),
         )
         .add_system_set(
             // Active when the initial state is popped off the stack
             SystemSet::on_exit(initial_state).with_system(Self::cleanup_board),
         )
+        .add_event::<events::BoardActionEvent>()
         .add_event::<events::TileTriggerEvent>()
         .add_event::<events::BoardCompletedEvent>()
         .add_event::<events::TileMarkEvent>()
-        .add_event::<events::MineExplodedEvent>();
+        .add_event::<events::TileChordEvent>()
+        .add_event::<events::MineExplodedEvent>()
+        .add_event::<events::TileUncoveredEvent>()
+        .add_event::<events::TileFlaggedEvent>()
+        .add_event::<events::BoardGeneratedEvent>()
+        .add_event::<events::TimerTickedEvent>()
+        .add_event::<events::GenerationFailedEvent>()
+        .add_event::<events::UndoEvent>()
+        .add_event::<events::RedoEvent>()
+        .add_event::<events::HintEvent>()
+        .add_event::<events::TileHintedEvent>()
+        .add_event::<events::TileCoveredEvent>()
+        .add_event::<events::ExplosionUndoneEvent>()
+        .init_resource::<CameraOptions>()
+        .init_resource::<CursorOptions>()
+        .init_resource::<GamepadOptions>()
+        .init_resource::<TouchOptions>()
+        .init_resource::<InputMap>()
+        .add_asset::<Puzzle>()
+        .init_asset_loader::<PuzzleLoader>();
 
         log::info!("Loaded board plugin");
         #[cfg(feature = "debug")]
         {
             app.register_inspectable::<Coordinates>()
                 .register_inspectable::<Neighbor>()
-                .register_inspectable::<Mine>()
-                .register_inspectable::<Uncovered>();
+                .register_inspectable::<Mine>();
         }
     }
 }
 
 impl<T> BoardPlugin<T> {
-    fn cleanup_board(mut commands: Commands, board: Res<Board>) {
-        commands.entity(board.entity).despawn_recursive();
+    fn cleanup_board(mut commands: Commands, board: Option<Res<Board>>) {
+        if let Some(board) = board {
+            commands.entity(board.entity).despawn_recursive();
+        }
         commands.remove_resource::<Board>();
+        commands.remove_resource::<BoardStats>();
+        commands.remove_resource::<BoardHistory>();
+        commands.remove_resource::<TileAtlas>();
+    }
+}
+
+/// Holds the board systems back until the board is created
+fn board_created(In(should_run): In<ShouldRun>, board: Option<Res<Board>>) -> ShouldRun {
+    match board {
+        Some(_) => should_run,
+        None => ShouldRun::No,
     }
 }
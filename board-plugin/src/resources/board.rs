@@ -1,81 +1,255 @@
-use crate::{bounds::Bounds2, components::Coordinates, resources::TileMap};
+use crate::{
+    bounds::Bounds2,
+    components::Coordinates,
+    events::BoardGeneratedEvent,
+    resources::{Generator, SafeArea, Tile, TileMap},
+};
 use bevy::{prelude::*, utils::HashMap};
+use fixedbitset::FixedBitSet;
+use serde::{Deserialize, Serialize};
+
+/// Mine placement waiting for the first uncovered tile
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct DeferredPlacement {
+    pub mine_count: u16,
+    pub safe_area: SafeArea,
+    pub generator: Generator,
+}
+
+/// Mark put on a covered tile
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TileMark {
+    /// Counts as a mine and keeps the tile from being uncovered
+    Flag,
+    /// Reminder only, neither counted as a mine nor keeping the tile from being uncovered
+    Question,
+}
+
+/// Progress of the game played on a board
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BoardStatus {
+    /// The game is ongoing and accepts input
+    Playing,
+    /// Every tile without a mine was uncovered
+    Won,
+    /// A mine exploded
+    Lost,
+}
+
+impl Default for BoardStatus {
+    fn default() -> Self {
+        Self::Playing
+    }
+}
 
 #[derive(Debug)]
 pub struct Board {
     pub tile_map: TileMap,
+    /// World bounds of the board as laid out, moving the board entity afterwards does not update them
     pub bounds: Bounds2,
     pub tile_size: f32,
-    pub covered_tiles: HashMap<Coordinates, Entity>,
+    pub tile_padding: f32,
+    /// Covered tiles, row by row from the bottom left
+    pub(crate) covered: FixedBitSet,
+    /// Cover sprites of the covered tiles, empty on chunked boards
+    pub covers: HashMap<Coordinates, Entity>,
+    /// Tiles waiting to be uncovered, their empty neighbors are queued in turn,
+    /// along with the id of the `BoardHistory` move they belong to
+    pub(crate) uncovering: Vec<(Coordinates, Option<usize>)>,
     pub marked_tiles: Vec<Coordinates>,
+    /// Covered tiles marked with a question mark
+    pub questioned_tiles: Vec<Coordinates>,
+    /// Does marking a flagged tile turn the flag into a question mark instead of removing it?
+    pub question_marks: bool,
     pub entity: Entity,
+    /// Seed the tile map was generated with
+    pub seed: u64,
+    /// Set while the tile map is still waiting for its mines
+    pub deferred_placement: Option<DeferredPlacement>,
+    pub status: BoardStatus,
+    /// Mine that exploded and lost the game, other mines uncovered along with it did not explode
+    pub exploded: Option<Coordinates>,
+    /// Set once the player uncovers a tile
+    pub(crate) started: bool,
+    /// Size of the chunks the tiles are rendered in, `None` if every tile is a sprite entity.
+    /// Chunked boards have no tile nor cover entities, only their chunks.
+    pub chunk_size: Option<u16>,
 }
 
 impl Board {
-    /// Translate a mouse position to board coordinates
-    pub fn convert_mouse_to_coordinates(
+    /// Size of the sprites drawn on a tile, i.e. the tile size without padding
+    pub fn tile_sprite_size(&self) -> f32 {
+        self.tile_size - self.tile_padding
+    }
+
+    /// Tile hit by a world space ray, such as `BoardCamera::screen_to_ray` for the cursor,
+    /// wherever the board entity is placed, rotated or scaled by its `GlobalTransform`
+    pub fn pick_tile(
         &self,
-        mouse_position: Vec2,
-        window: &Window,
+        board_transform: &GlobalTransform,
+        (origin, direction): (Vec3, Vec3),
     ) -> Option<Coordinates> {
-        // Window to world space
-        // World space has origin at center of board, but window space has origin at bottom left
-        let window_size = Vec2::new(window.width(), window.height());
-        let world_position = mouse_position - window_size / 2.;
+        // World space to board space, where the board lies on the Z = 0 plane
+        let world_to_board = board_transform.compute_matrix().inverse();
+        let origin = world_to_board.transform_point3(origin);
+        let direction = world_to_board.transform_vector3(direction);
+        if direction.z.abs() <= f32::EPSILON {
+            // The ray runs alongside the board
+            return None;
+        }
+        let hit = origin - direction * (origin.z / direction.z);
+        self.convert_local_to_coordinates(hit.truncate())
+    }
 
-        if !self.bounds.in_bounds(world_position) {
+    /// Translate a position in board space, with origin at the bottom left of the board, to board coordinates
+    pub fn convert_local_to_coordinates(&self, local_position: Vec2) -> Option<Coordinates> {
+        let local_bounds = Bounds2 {
+            position: Vec2::ZERO,
+            size: self.bounds.size,
+        };
+        if !local_bounds.in_bounds(local_position) {
             return None;
         }
 
-        // World space to board space
-        let absolute_coordinates = world_position - self.bounds.position;
         Some(Coordinates {
-            x: (absolute_coordinates.x / self.tile_size) as u16,
-            y: (absolute_coordinates.y / self.tile_size) as u16,
+            x: (local_position.x / self.tile_size) as u16,
+            y: (local_position.y / self.tile_size) as u16,
         })
     }
 
-    /// Retrieve a tile entity if it is still covered and unmarked.
-    pub fn get_covered_tile(&self, coordinates: Coordinates) -> Option<Entity> {
-        if self.is_marked(coordinates) {
-            None
-        } else {
-            self.covered_tiles.get(&coordinates).copied()
+    /// Smallest world space rectangle around the board, as its bottom left and top right corners,
+    /// for the board entity placed at `board_transform`
+    pub fn world_rect(&self, board_transform: &GlobalTransform) -> (Vec2, Vec2) {
+        let board_to_world = board_transform.compute_matrix();
+        let size = self.bounds.size;
+        [
+            Vec2::ZERO,
+            Vec2::new(size.x, 0.),
+            Vec2::new(0., size.y),
+            size,
+        ]
+        .into_iter()
+        .map(|corner| {
+            board_to_world
+                .transform_point3(corner.extend(0.))
+                .truncate()
+        })
+        .fold(
+            (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |(min, max), corner| (min.min(corner), max.max(corner)),
+        )
+    }
+
+    pub fn is_covered(&self, coordinates: Coordinates) -> bool {
+        self.tile_map
+            .index_of(coordinates)
+            .map_or(false, |index| self.covered.contains(index))
+    }
+
+    /// Covered tiles, row by row from the bottom left
+    pub fn covered_tiles(&self) -> impl Iterator<Item = Coordinates> + '_ {
+        let width = self.tile_map.width as usize;
+        self.covered.ones().map(move |index| Coordinates {
+            x: (index % width) as u16,
+            y: (index / width) as u16,
+        })
+    }
+
+    pub fn covered_count(&self) -> usize {
+        self.covered.count_ones(..)
+    }
+
+    /// Is the tile still covered and unmarked?
+    pub fn is_uncoverable(&self, coordinates: Coordinates) -> bool {
+        !self.is_marked(coordinates) && self.is_covered(coordinates)
+    }
+
+    /// Is any tile waiting to be uncovered?
+    pub fn is_uncovering(&self) -> bool {
+        !self.uncovering.is_empty()
+    }
+
+    /// Covers a tile again, unmarked
+    pub(crate) fn cover_tile(&mut self, coordinates: Coordinates) {
+        if let Some(index) = self.tile_map.index_of(coordinates) {
+            self.covered.insert(index);
         }
     }
 
-    /// Try to uncover a tile
-    /// Will remove a mark without uncovering the tile and return `None`
-    pub fn uncover_tile(&mut self, coordinates: Coordinates) -> Option<Entity> {
+    /// Try to uncover a tile, returns whether it was uncovered.
+    /// Will remove a flag without uncovering the tile, question marks are uncovered along with their tile.
+    /// The cover sprite is left in `covers` to be despawned.
+    pub fn uncover_tile(&mut self, coordinates: Coordinates) -> bool {
         if self.is_marked(coordinates) {
             self.unmark_tile(coordinates)
                 .expect("Failed to unmark tile that is saved as marked tile");
-            None
-        } else {
-            self.covered_tiles.remove(&coordinates)
+            return false;
+        }
+        self.questioned_tiles
+            .retain(|questioned| *questioned != coordinates);
+        match self.tile_map.index_of(coordinates) {
+            Some(index) if self.covered.contains(index) => {
+                self.covered.set(index, false);
+                true
+            }
+            _ => false,
         }
     }
 
-    /// Retrieve adjacent covered tiles
-    pub fn get_covered_neighbors(&self, coordinates: Coordinates) -> Vec<Entity> {
+    /// Retrieve adjacent covered and unmarked tiles
+    pub fn get_covered_neighbors(&self, coordinates: Coordinates) -> Vec<Coordinates> {
         self.tile_map
             .safe_square_at(coordinates)
-            .filter_map(|coordinates| self.get_covered_tile(coordinates))
+            .filter(|coordinates| self.is_uncoverable(*coordinates))
             .collect()
     }
 
-    /// Toggles marked state of a tile and returns the new state
-    pub fn toggle_mark(&mut self, coordinates: Coordinates) -> Option<(Entity, bool)> {
-        let entity = *self.covered_tiles.get(&coordinates)?;
-        let new_state = if self.is_marked(coordinates) {
-            self.unmark_tile(coordinates)
-                .expect("Failed to unmark tile that is saved as marked tile");
-            false
+    /// Retrieve the covered neighbors to uncover when chording an uncovered tile.
+    /// Returns `None` unless the tile neighbors exactly as many marked tiles as mines.
+    pub fn get_chord_tiles(&self, coordinates: Coordinates) -> Option<Vec<Coordinates>> {
+        if self.is_covered(coordinates) {
+            return None;
+        }
+        let count = match self.tile_map.get(coordinates)? {
+            Tile::Neighbor(count) => *count as usize,
+            _ => return None,
+        };
+        let marked_neighbors = self
+            .tile_map
+            .safe_square_at(coordinates)
+            .filter(|coordinates| self.is_marked(*coordinates))
+            .count();
+        if marked_neighbors == count {
+            Some(self.get_covered_neighbors(coordinates))
         } else {
-            self.marked_tiles.push(coordinates);
-            true
+            None
+        }
+    }
+
+    /// Cycles the mark of a covered tile from none to a flag, then to a question mark if enabled, and returns the new mark
+    pub fn toggle_mark(&mut self, coordinates: Coordinates) -> Option<Option<TileMark>> {
+        let mark = match self.mark_at(coordinates) {
+            None => Some(TileMark::Flag),
+            Some(TileMark::Flag) if self.question_marks => Some(TileMark::Question),
+            Some(_) => None,
         };
-        Some((entity, new_state))
+        self.set_mark(coordinates, mark).then_some(mark)
+    }
+
+    /// Replaces the mark of a covered tile, returns whether the tile is covered
+    pub fn set_mark(&mut self, coordinates: Coordinates, mark: Option<TileMark>) -> bool {
+        if !self.is_covered(coordinates) {
+            return false;
+        }
+        self.unmark_tile(coordinates);
+        self.questioned_tiles
+            .retain(|questioned| *questioned != coordinates);
+        match mark {
+            Some(TileMark::Flag) => self.marked_tiles.push(coordinates),
+            Some(TileMark::Question) => self.questioned_tiles.push(coordinates),
+            None => (),
+        }
+        true
     }
 
     fn unmark_tile(&mut self, coordinates: Coordinates) -> Option<Coordinates> {
@@ -84,11 +258,156 @@ impl Board {
             .next()
     }
 
+    pub fn is_playing(&self) -> bool {
+        self.status == BoardStatus::Playing
+    }
+
+    /// Every tile still covered is a mine, no matter how the tiles are marked
     pub fn is_completed(&self) -> bool {
-        self.tile_map.mine_count as usize == self.covered_tiles.len()
+        self.deferred_placement.is_none()
+            && self.tile_map.mine_count as usize == self.covered_count()
+            && self
+                .covered_tiles()
+                .all(|coordinates| self.tile_map.is_mine_at(coordinates))
+    }
+
+    pub fn generated_event(&self) -> BoardGeneratedEvent {
+        BoardGeneratedEvent {
+            width: self.tile_map.width,
+            height: self.tile_map.height,
+            mine_count: self.tile_map.mine_count,
+            seed: self.seed,
+        }
+    }
+
+    /// Has the player uncovered any tile yet? Tiles revealed by a puzzle or a safe start do not count
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /// Mines on the board, including mines still waiting for deferred placement
+    pub fn mine_count(&self) -> u16 {
+        match self.deferred_placement {
+            Some(placement) => placement.mine_count,
+            None => self.tile_map.mine_count,
+        }
     }
 
-    fn is_marked(&self, coordinates: Coordinates) -> bool {
+    /// Mine count minus marked tiles
+    pub fn remaining_mines(&self) -> i32 {
+        self.mine_count() as i32 - self.marked_tiles.len() as i32
+    }
+
+    pub fn is_marked(&self, coordinates: Coordinates) -> bool {
         self.marked_tiles.contains(&coordinates)
     }
+
+    pub fn is_questioned(&self, coordinates: Coordinates) -> bool {
+        self.questioned_tiles.contains(&coordinates)
+    }
+
+    pub fn mark_at(&self, coordinates: Coordinates) -> Option<TileMark> {
+        if self.is_marked(coordinates) {
+            Some(TileMark::Flag)
+        } else if self.is_questioned(coordinates) {
+            Some(TileMark::Question)
+        } else {
+            None
+        }
+    }
+}
+
+/// Playing board of unit tiles over the given map, without covered tiles nor marks
+#[cfg(test)]
+pub(crate) fn test_board(tile_map: TileMap) -> Board {
+    let tile_count = tile_map.width as usize * tile_map.height as usize;
+    Board {
+        bounds: Bounds2 {
+            position: Vec2::ZERO,
+            size: Vec2::new(tile_map.width as f32, tile_map.height as f32),
+        },
+        tile_map,
+        tile_size: 1.,
+        tile_padding: 0.,
+        covered: FixedBitSet::with_capacity(tile_count),
+        covers: HashMap::default(),
+        uncovering: Vec::new(),
+        marked_tiles: Vec::new(),
+        questioned_tiles: Vec::new(),
+        question_marks: false,
+        entity: Entity::from_raw(0),
+        seed: 0,
+        deferred_placement: None,
+        status: BoardStatus::Playing,
+        exploded: None,
+        started: false,
+        chunk_size: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn picks_tiles_of_transformed_board() {
+        let board = Board {
+            bounds: Bounds2 {
+                position: Vec2::ZERO,
+                size: Vec2::new(40., 20.),
+            },
+            tile_size: 10.,
+            ..test_board(TileMap::empty(4, 2))
+        };
+        // Looking down the Z axis like a 2D camera
+        let ray = |x: f32, y: f32| (Vec3::new(x, y, 1000.), -Vec3::Z);
+
+        let transform = GlobalTransform::from_xyz(100., 100., 5.);
+        assert_eq!(
+            board.pick_tile(&transform, ray(135., 115.)),
+            Some(Coordinates { x: 3, y: 1 })
+        );
+        assert_eq!(board.pick_tile(&transform, ray(95., 115.)), None);
+
+        // Rotated a quarter turn counterclockwise and scaled twice
+        let transform = GlobalTransform {
+            rotation: Quat::from_rotation_z(FRAC_PI_2),
+            scale: Vec3::splat(2.),
+            ..Default::default()
+        };
+        assert_eq!(
+            board.pick_tile(&transform, ray(-5., 75.)),
+            Some(Coordinates { x: 3, y: 0 })
+        );
+        let (min, max) = board.world_rect(&transform);
+        assert!(min.abs_diff_eq(Vec2::new(-40., 0.), 1e-3));
+        assert!(max.abs_diff_eq(Vec2::new(0., 80.), 1e-3));
+    }
+
+    #[test]
+    fn cycles_marks_through_question_marks() {
+        let coordinates = Coordinates { x: 1, y: 0 };
+        let mut board = Board {
+            question_marks: true,
+            ..test_board(TileMap::from_mines(2, 1, [Coordinates { x: 0, y: 0 }]))
+        };
+        board.cover_tile(coordinates);
+
+        let marks: Vec<_> = (0..3)
+            .map(|_| board.toggle_mark(coordinates).unwrap())
+            .collect();
+        assert_eq!(
+            marks,
+            [Some(TileMark::Flag), Some(TileMark::Question), None]
+        );
+
+        board.toggle_mark(coordinates);
+        board.toggle_mark(coordinates);
+        // Question marks are not counted and do not keep the tile covered
+        assert_eq!(board.remaining_mines(), 1);
+        assert!(board.uncover_tile(coordinates));
+        assert!(!board.is_covered(coordinates));
+        assert!(board.questioned_tiles.is_empty());
+    }
 }
@@ -0,0 +1,114 @@
+use crate::components::Coordinates;
+use crate::resources::TileMark;
+
+/// Changes made to the board by a single uncover, chord or mark action
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BoardMove {
+    /// Tiles uncovered by the action, including propagation, with the mark they had
+    pub uncovered: Vec<(Coordinates, Option<TileMark>)>,
+    /// Tile marked by the action, with its mark before and after
+    pub marked: Option<(Coordinates, Option<TileMark>, Option<TileMark>)>,
+    /// Mine that exploded, losing the game
+    pub exploded: Option<Coordinates>,
+}
+
+impl BoardMove {
+    /// Does the move change nothing, e.g. chording a tile with missing flags?
+    pub fn is_empty(&self) -> bool {
+        self.uncovered.is_empty() && self.marked.is_none() && self.exploded.is_none()
+    }
+}
+
+/// Moves played on the current board, undone and redone with `UndoEvent` and `RedoEvent`.
+/// Inserted along with the `Board` resource unless `BoardOptions::undo` is disabled.
+#[derive(Debug, Clone, Default)]
+pub struct BoardHistory {
+    done: Vec<BoardMove>,
+    undone: Vec<BoardMove>,
+    /// Does undoing a move mark the game as assisted?
+    pub assisted: bool,
+}
+
+impl BoardHistory {
+    pub fn new(assisted: bool) -> Self {
+        Self {
+            assisted,
+            ..Default::default()
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.done.iter().any(|done| !done.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Starts recording a new move, dropping the undone moves, and returns its id
+    pub(crate) fn push(&mut self) -> usize {
+        self.done.push(BoardMove::default());
+        self.undone.clear();
+        self.done.len() - 1
+    }
+
+    /// Move being recorded
+    pub(crate) fn current(&mut self) -> Option<&mut BoardMove> {
+        self.done.last_mut()
+    }
+
+    /// Move of the given id, until it is undone
+    pub(crate) fn get_mut(&mut self, id: usize) -> Option<&mut BoardMove> {
+        self.done.get_mut(id)
+    }
+
+    /// Takes back the last move that changed the board
+    pub(crate) fn undo(&mut self) -> Option<BoardMove> {
+        while let Some(done) = self.done.pop() {
+            if !done.is_empty() {
+                self.undone.push(done.clone());
+                return Some(done);
+            }
+        }
+        None
+    }
+
+    /// Takes back the last undone move
+    pub(crate) fn redo(&mut self) -> Option<BoardMove> {
+        let undone = self.undone.pop()?;
+        self.done.push(undone.clone());
+        Some(undone)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn undoes_and_redoes_moves() {
+        let uncovered = Coordinates { x: 1, y: 1 };
+        let mut history = BoardHistory::new(false);
+        let id = history.push();
+        // Chording without enough flags changes nothing
+        history.push();
+        assert!(!history.can_undo());
+        // Propagation still adds tiles to the move that started it
+        history
+            .get_mut(id)
+            .unwrap()
+            .uncovered
+            .push((uncovered, None));
+        assert!(history.can_undo());
+
+        let undone = history.undo().unwrap();
+        assert_eq!(undone.uncovered, vec![(uncovered, None)]);
+        assert!(!history.can_undo());
+        assert_eq!(history.redo(), Some(undone));
+        assert!(!history.can_redo());
+
+        history.undo();
+        history.push();
+        assert!(!history.can_redo());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::assets::puzzle::Puzzle;
+use crate::resources::GenerationError;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -41,6 +43,121 @@ impl Default for BoardPosition {
     }
 }
 
+/// Area guaranteed to be free of mines around the first uncovered tile
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SafeArea {
+    /// Only the uncovered tile itself
+    Tile,
+    /// The uncovered tile and its 8 neighbors, so the first click always lands on an empty tile and opens up an area
+    Square,
+    /// Every tile up to two tiles away, so the first click opens up at least a 5x5 area away from the edges
+    Opening,
+}
+
+impl SafeArea {
+    /// Distance from the uncovered tile that is kept free of mines
+    pub fn radius(&self) -> u16 {
+        match self {
+            Self::Tile => 0,
+            Self::Square => 1,
+            Self::Opening => 2,
+        }
+    }
+
+    /// Tiles kept free of mines on a map of the given size, at most
+    pub fn max_tile_count(&self, (width, height): (u16, u16)) -> usize {
+        let side = 2 * self.radius() as usize + 1;
+        side.min(width as usize) * side.min(height as usize)
+    }
+}
+
+/// Mine layout generation strategy
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Generator {
+    /// Mines are placed at random, the player may have to guess
+    Random,
+    /// Only accepts layouts that can be fully solved by pure deduction from the safe start,
+    /// i.e. the first uncovered tile with `first_click_safety`, or the `safe_start` tile otherwise.
+    /// Requires one of them, mines are placed at random without a start to solve from.
+    /// Falls back to the last random layout and sends a `GenerationFailedEvent` if none is found within `max_attempts`.
+    /// Every attempt runs the solver over the whole map, all within the frame the board is created,
+    /// or the first tile is uncovered with `first_click_safety`, so large maps call for a low budget.
+    NoGuess { max_attempts: u32 },
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self::Random
+    }
+}
+
+/// Tile rendering strategy
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TileRendering {
+    /// Every tile, cover, content and flag is a sprite entity
+    Entities,
+    /// Tiles are drawn by a single mesh per square chunk of `chunk_size` tiles,
+    /// only chunks with changed tiles are updated
+    Chunked { chunk_size: u16 },
+    /// Chunked rendering for boards of more than `max_entity_tiles` tiles, entities otherwise
+    Auto { max_entity_tiles: u32 },
+}
+
+impl Default for TileRendering {
+    fn default() -> Self {
+        Self::Auto {
+            max_entity_tiles: 10_000,
+        }
+    }
+}
+
+impl TileRendering {
+    /// Chunk size used by a board of the given size, `None` when rendering entities
+    pub fn chunk_size(&self, (width, height): (u16, u16)) -> Option<u16> {
+        match *self {
+            Self::Entities => None,
+            Self::Chunked { chunk_size } => Some(chunk_size.max(1)),
+            Self::Auto { max_entity_tiles } => {
+                (width as u32 * height as u32 > max_entity_tiles).then_some(32)
+            }
+        }
+    }
+}
+
+/// Availability of undo and redo, see `BoardHistory`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum UndoMode {
+    Disabled,
+    /// Moves can be undone freely
+    Enabled,
+    /// Moves can be undone, which marks the game as assisted in its `BoardStats`
+    Assisted,
+}
+
+impl Default for UndoMode {
+    fn default() -> Self {
+        Self::Disabled
+    }
+}
+
+/// Origin of the board layout
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoardSource {
+    /// Mines are placed according to the generation options
+    Generated,
+    /// The board is built from a puzzle asset, and rebuilt whenever the asset is modified.
+    /// The board is only created once the puzzle is loaded, or generated if it fails to load
+    /// or the handle is neither from the asset server nor added to the puzzle assets.
+    /// Map size, mine count and generation options are ignored otherwise.
+    Puzzle(Handle<Puzzle>),
+}
+
+impl Default for BoardSource {
+    fn default() -> Self {
+        Self::Generated
+    }
+}
+
 /// Board generation options.
 /// Must be used as a resource.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,7 +173,32 @@ pub struct BoardOptions {
     /// Padding between tiles
     pub tile_padding: f32,
     /// Does the board generate a safe place to start from?
+    /// Ignored when `first_click_safety` is set.
     pub safe_start: bool,
+    /// Defers mine placement until the first tile is uncovered and keeps the given area around it free of mines.
+    /// Mines are placed as soon as the board is spawned if `None`.
+    #[serde(default)]
+    pub first_click_safety: Option<SafeArea>,
+    /// Mine layout generation strategy
+    #[serde(default)]
+    pub generator: Generator,
+    /// Seed used for mine generation.
+    /// The same seed with the same map size and mine count always generates the same board.
+    /// A random seed is picked if `None`.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Tile rendering strategy
+    #[serde(default)]
+    pub rendering: TileRendering,
+    /// Does marking a flagged tile put a question mark on it before clearing it?
+    #[serde(default)]
+    pub question_marks: bool,
+    /// Can moves be undone?
+    #[serde(default)]
+    pub undo: UndoMode,
+    /// Origin of the board layout, not serialized
+    #[serde(skip)]
+    pub source: BoardSource,
 }
 
 impl Default for BoardOptions {
@@ -68,6 +210,105 @@ impl Default for BoardOptions {
             tile_size: Default::default(),
             tile_padding: 0.,
             safe_start: false,
+            first_click_safety: None,
+            generator: Default::default(),
+            seed: None,
+            rendering: Default::default(),
+            question_marks: false,
+            undo: Default::default(),
+            source: Default::default(),
+        }
+    }
+}
+
+impl BoardOptions {
+    /// Mine count, reduced so that the map keeps room for the first click safe area
+    pub fn placeable_mine_count(&self) -> u16 {
+        let tile_count = self.map_size.0 as usize * self.map_size.1 as usize;
+        let safe_tiles = self
+            .first_click_safety
+            .map_or(0, |safe_area| safe_area.max_tile_count(self.map_size));
+        let free_tiles = tile_count.saturating_sub(safe_tiles);
+        self.mine_count
+            .min(free_tiles.min(u16::MAX as usize) as u16)
+    }
+
+    /// Generator to place mines with, `NoGuess` is rejected without a start the player gets to see
+    pub fn checked_generator(&self) -> Result<Generator, GenerationError> {
+        match self.generator {
+            Generator::NoGuess { .. } if !self.safe_start && self.first_click_safety.is_none() => {
+                Err(GenerationError::NoGuessWithoutStart)
+            }
+            generator => Ok(generator),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn leaves_room_for_the_safe_area() {
+        let options = BoardOptions {
+            map_size: (6, 4),
+            mine_count: 20,
+            first_click_safety: Some(SafeArea::Opening),
+            ..Default::default()
+        };
+        assert_eq!(options.placeable_mine_count(), 4);
+        let options = BoardOptions {
+            first_click_safety: None,
+            ..options
+        };
+        assert_eq!(options.placeable_mine_count(), 20);
+    }
+
+    #[test]
+    fn rejects_no_guess_without_visible_start() {
+        let generator = Generator::NoGuess { max_attempts: 10 };
+        let options = BoardOptions {
+            generator,
+            ..Default::default()
+        };
+        assert_eq!(
+            options.checked_generator(),
+            Err(GenerationError::NoGuessWithoutStart)
+        );
+        for options in [
+            BoardOptions {
+                safe_start: true,
+                ..options.clone()
+            },
+            BoardOptions {
+                first_click_safety: Some(SafeArea::Tile),
+                ..options.clone()
+            },
+        ] {
+            assert_eq!(options.checked_generator(), Ok(generator));
+        }
+    }
+
+    #[test]
+    fn loads_options_saved_without_newer_fields() {
+        let ron = ron::to_string(&BoardOptions {
+            seed: Some(3),
+            ..Default::default()
+        })
+        .unwrap();
+        let ron = [
+            "seed:Some(3),",
+            "first_click_safety:None,",
+            "generator:Random,",
+        ]
+        .into_iter()
+        .fold(ron, |ron, field| {
+            assert!(ron.contains(field));
+            ron.replacen(field, "", 1)
+        });
+        let options: BoardOptions = ron::from_str(&ron).unwrap();
+        assert_eq!(options.seed, None);
+        assert_eq!(options.first_click_safety, None);
+        assert_eq!(options.generator, Generator::Random);
+    }
+}
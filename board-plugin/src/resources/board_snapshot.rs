@@ -0,0 +1,377 @@
+use crate::components::Coordinates;
+use crate::resources::{Board, BoardStats, BoardStatus, DeferredPlacement, Tile, TileMap};
+use fixedbitset::FixedBitSet;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, io, path::Path};
+
+/// Serializable state of a game in progress.
+///
+/// Insert it as a resource before the board is created to resume the game instead of generating a new one.
+/// The resource is consumed by the board creation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardSnapshot {
+    pub tile_map: TileMap,
+    pub covered_tiles: Vec<Coordinates>,
+    pub marked_tiles: Vec<Coordinates>,
+    #[serde(default)]
+    pub questioned_tiles: Vec<Coordinates>,
+    /// Tiles still waiting to be uncovered, when saved while an empty area propagates
+    #[serde(default)]
+    pub uncovering_tiles: Vec<Coordinates>,
+    pub status: BoardStatus,
+    /// Has the player uncovered a tile yet? Older snapshots without it were only saved for started games
+    #[serde(default = "started_by_default")]
+    pub started: bool,
+    pub stats: BoardStats,
+    pub seed: u64,
+    pub deferred_placement: Option<DeferredPlacement>,
+}
+
+fn started_by_default() -> bool {
+    true
+}
+
+/// Error raised when saving or loading a snapshot
+#[derive(Debug)]
+pub enum SnapshotError {
+    #[cfg(not(target_arch = "wasm32"))]
+    Io(io::Error),
+    Ron(ron::Error),
+    /// The tile map does not hold `width * height` tiles
+    TileCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// The mine count of the tile map does not match its mine tiles
+    MineCountMismatch {
+        expected: u16,
+        found: usize,
+    },
+    /// More tiles are covered than the board holds
+    TooManyCoveredTiles {
+        count: usize,
+    },
+    /// A covered tile lies outside of the board
+    CoveredTileOutOfBounds(Coordinates),
+    /// A tile does not count the mines around it
+    NeighborCountMismatch {
+        coordinates: Coordinates,
+        expected: u8,
+        found: u8,
+    },
+    /// A flagged or questioned tile is not a covered tile of the board
+    MarkedTileNotCovered(Coordinates),
+    /// A tile waiting to be uncovered lies outside of the board
+    UncoveringTileOutOfBounds(Coordinates),
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Io(error) => write!(f, "Failed to access board snapshot file: {}", error),
+            Self::Ron(error) => write!(f, "Invalid board snapshot: {}", error),
+            Self::TileCountMismatch { expected, found } => write!(
+                f,
+                "Invalid board snapshot: expected {} tiles, found {}",
+                expected, found
+            ),
+            Self::MineCountMismatch { expected, found } => write!(
+                f,
+                "Invalid board snapshot: expected {} mines, found {}",
+                expected, found
+            ),
+            Self::TooManyCoveredTiles { count } => write!(
+                f,
+                "Invalid board snapshot: {} covered tiles is more than the board holds",
+                count
+            ),
+            Self::CoveredTileOutOfBounds(coordinates) => write!(
+                f,
+                "Invalid board snapshot: covered tile {} is outside of the board",
+                coordinates
+            ),
+            Self::NeighborCountMismatch {
+                coordinates,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Invalid board snapshot: tile {} counts {} neighboring mines instead of {}",
+                coordinates, found, expected
+            ),
+            Self::MarkedTileNotCovered(coordinates) => write!(
+                f,
+                "Invalid board snapshot: marked tile {} is not a covered tile of the board",
+                coordinates
+            ),
+            Self::UncoveringTileOutOfBounds(coordinates) => write!(
+                f,
+                "Invalid board snapshot: tile {} waiting to be uncovered is outside of the board",
+                coordinates
+            ),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ron::Error> for SnapshotError {
+    fn from(error: ron::Error) -> Self {
+        Self::Ron(error)
+    }
+}
+
+impl BoardSnapshot {
+    /// Captures the current state of a board, along with the tiles it is still uncovering
+    pub fn new(board: &Board, stats: &BoardStats) -> Self {
+        let mut covered_tiles: Vec<_> = board.covered_tiles().collect();
+        covered_tiles.sort_unstable();
+        Self {
+            tile_map: board.tile_map.clone(),
+            covered_tiles,
+            marked_tiles: board.marked_tiles.clone(),
+            questioned_tiles: board.questioned_tiles.clone(),
+            uncovering_tiles: board
+                .uncovering
+                .iter()
+                .map(|(coordinates, _)| *coordinates)
+                .collect(),
+            status: board.status,
+            started: board.started,
+            stats: stats.clone(),
+            seed: board.seed,
+            deferred_placement: board.deferred_placement,
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, SnapshotError> {
+        Ok(ron::to_string(self)?)
+    }
+
+    /// Reads a snapshot, rejecting tile maps and covered tiles that do not add up
+    pub fn from_ron(ron: &str) -> Result<Self, SnapshotError> {
+        let snapshot: Self = ron::from_str(ron)?;
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+
+    fn validate(&self) -> Result<(), SnapshotError> {
+        let tile_map = &self.tile_map;
+        let tile_count = tile_map.width as usize * tile_map.height as usize;
+        if tile_map.stored_tile_count() != tile_count {
+            return Err(SnapshotError::TileCountMismatch {
+                expected: tile_count,
+                found: tile_map.stored_tile_count(),
+            });
+        }
+        let mines = tile_map
+            .iter_with_coordinates()
+            .filter(|(_, tile)| **tile == Tile::Mine)
+            .count();
+        if mines != tile_map.mine_count as usize {
+            return Err(SnapshotError::MineCountMismatch {
+                expected: tile_map.mine_count,
+                found: mines,
+            });
+        }
+        if self.covered_tiles.len() > tile_count {
+            return Err(SnapshotError::TooManyCoveredTiles {
+                count: self.covered_tiles.len(),
+            });
+        }
+        let mut covered = FixedBitSet::with_capacity(tile_count);
+        for coordinates in &self.covered_tiles {
+            match tile_map.index_of(*coordinates) {
+                Some(index) => covered.insert(index),
+                None => return Err(SnapshotError::CoveredTileOutOfBounds(*coordinates)),
+            }
+        }
+        for (coordinates, tile) in tile_map.iter_with_coordinates() {
+            let found = match tile {
+                Tile::Mine => continue,
+                Tile::Empty => 0,
+                Tile::Neighbor(count) => *count,
+            };
+            let expected = tile_map.neighbor_count_at(coordinates);
+            let expected_tile = match expected {
+                0 => Tile::Empty,
+                count => Tile::Neighbor(count),
+            };
+            if *tile != expected_tile {
+                return Err(SnapshotError::NeighborCountMismatch {
+                    coordinates,
+                    expected,
+                    found,
+                });
+            }
+        }
+        let is_covered = |coordinates: &Coordinates| {
+            tile_map
+                .index_of(*coordinates)
+                .map_or(false, |index| covered.contains(index))
+        };
+        let mut marks = self.marked_tiles.iter().chain(&self.questioned_tiles);
+        if let Some(&coordinates) = marks.find(|coordinates| !is_covered(*coordinates)) {
+            return Err(SnapshotError::MarkedTileNotCovered(coordinates));
+        }
+        match self
+            .uncovering_tiles
+            .iter()
+            .find(|coordinates| tile_map.index_of(**coordinates).is_none())
+        {
+            Some(&coordinates) => Err(SnapshotError::UncoveringTileOutOfBounds(coordinates)),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the snapshot to a file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        fs::write(path, self.to_ron()?)?;
+        Ok(())
+    }
+
+    /// Reads a snapshot from a file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_snapshot() -> BoardSnapshot {
+        let mut tile_map = TileMap::empty(4, 3);
+        tile_map.place_mines_seeded(3, 5);
+        BoardSnapshot {
+            tile_map: tile_map.clone(),
+            covered_tiles: vec![Coordinates { x: 1, y: 2 }, Coordinates { x: 3, y: 0 }],
+            marked_tiles: vec![Coordinates { x: 3, y: 0 }],
+            questioned_tiles: vec![Coordinates { x: 1, y: 2 }],
+            uncovering_tiles: vec![Coordinates { x: 0, y: 1 }],
+            status: BoardStatus::Playing,
+            started: true,
+            stats: BoardStats {
+                left_clicks: 4,
+                ..Default::default()
+            },
+            seed: 5,
+            deferred_placement: None,
+        }
+    }
+
+    #[test]
+    fn survives_ron_round_trip() {
+        let snapshot = test_snapshot();
+        let tile_map = snapshot.tile_map.clone();
+        let loaded = BoardSnapshot::from_ron(&snapshot.to_ron().unwrap()).unwrap();
+        assert_eq!(loaded.tile_map, tile_map);
+        assert_eq!(loaded.covered_tiles, snapshot.covered_tiles);
+        assert_eq!(loaded.marked_tiles, snapshot.marked_tiles);
+        assert_eq!(loaded.questioned_tiles, snapshot.questioned_tiles);
+        assert_eq!(loaded.uncovering_tiles, snapshot.uncovering_tiles);
+        assert_eq!(loaded.stats.left_clicks, 4);
+        assert_eq!(loaded.seed, 5);
+    }
+
+    #[test]
+    fn resumes_older_snapshots_as_started() {
+        let ron = test_snapshot().to_ron().unwrap();
+        assert!(ron.contains("started:true,"));
+        let loaded = BoardSnapshot::from_ron(&ron.replacen("started:true,", "", 1)).unwrap();
+        assert!(loaded.started);
+    }
+
+    #[test]
+    fn rejects_inconsistent_snapshots() {
+        let ron = test_snapshot().to_ron().unwrap();
+        let load = |from: &str, to: &str| {
+            assert!(ron.contains(from));
+            BoardSnapshot::from_ron(&ron.replacen(from, to, 1))
+        };
+        assert!(matches!(
+            load("width:4", "width:5"),
+            Err(SnapshotError::TileCountMismatch {
+                expected: 15,
+                found: 12
+            })
+        ));
+        assert!(matches!(
+            load("mine_count:3", "mine_count:4"),
+            Err(SnapshotError::MineCountMismatch {
+                expected: 4,
+                found: 3
+            })
+        ));
+        assert!(matches!(
+            load("(x:3,y:0)", "(x:4,y:0)"),
+            Err(SnapshotError::CoveredTileOutOfBounds(Coordinates {
+                x: 4,
+                y: 0
+            }))
+        ));
+    }
+
+    #[test]
+    fn rejects_inconsistent_tiles_and_marks() {
+        let load = |snapshot: BoardSnapshot| BoardSnapshot::from_ron(&snapshot.to_ron().unwrap());
+        let mut snapshot = test_snapshot();
+        let (coordinates, expected) = snapshot
+            .tile_map
+            .iter_with_coordinates()
+            .find(|(_, tile)| **tile != Tile::Mine)
+            .map(|(coordinates, _)| {
+                (
+                    coordinates,
+                    snapshot.tile_map.neighbor_count_at(coordinates),
+                )
+            })
+            .unwrap();
+        *snapshot.tile_map.get_mut(coordinates).unwrap() = Tile::Neighbor(expected + 1);
+        assert!(matches!(
+            load(snapshot),
+            Err(SnapshotError::NeighborCountMismatch { expected: count, found, .. })
+                if count == expected && found == expected + 1
+        ));
+
+        let uncovered = Coordinates { x: 0, y: 0 };
+        let mut snapshot = test_snapshot();
+        snapshot.questioned_tiles.push(uncovered);
+        assert!(matches!(
+            load(snapshot),
+            Err(SnapshotError::MarkedTileNotCovered(coordinates)) if coordinates == uncovered
+        ));
+        let mut snapshot = test_snapshot();
+        snapshot.marked_tiles.push(Coordinates { x: 9, y: 0 });
+        assert!(matches!(
+            load(snapshot),
+            Err(SnapshotError::MarkedTileNotCovered(Coordinates {
+                x: 9,
+                y: 0
+            }))
+        ));
+
+        let mut snapshot = test_snapshot();
+        snapshot.uncovering_tiles.push(Coordinates { x: 0, y: 3 });
+        assert!(matches!(
+            load(snapshot),
+            Err(SnapshotError::UncoveringTileOutOfBounds(Coordinates {
+                x: 0,
+                y: 3
+            }))
+        ));
+    }
+}
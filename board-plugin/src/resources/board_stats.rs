@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Progress of the current game, kept up to date by the board plugin for HUDs.
+/// Inserted along with the `Board` resource.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BoardStats {
+    /// Time played since the first uncovered tile.
+    /// Paused while the board state is not on top of the state stack and once the game is over.
+    pub elapsed: Duration,
+    /// Uncover actions, i.e. left clicks
+    pub left_clicks: u32,
+    /// Mark actions, i.e. right clicks
+    pub right_clicks: u32,
+    /// Chord actions
+    pub chord_clicks: u32,
+    /// Mine count minus marked tiles. Negative if more tiles are marked than there are mines.
+    pub remaining_mines: i32,
+    /// Hints given, see `HintEvent`. The tiles they uncover are not counted as clicks.
+    #[serde(default)]
+    pub hints: u32,
+    /// Whether a move was undone, see `UndoMode::Assisted`
+    #[serde(default)]
+    pub assisted: bool,
+}
+
+impl BoardStats {
+    pub fn clicks(&self) -> u32 {
+        self.left_clicks + self.right_clicks + self.chord_clicks
+    }
+}
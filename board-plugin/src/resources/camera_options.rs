@@ -0,0 +1,30 @@
+/// Pan and zoom options of the `BoardCamera`, bound to inputs by the `InputMap`.
+/// Inserted with its defaults by the `BoardPlugin`, the drag threshold also tells clicks and taps from drags.
+#[derive(Debug, Clone)]
+pub struct CameraOptions {
+    /// Distance in pixels the cursor must move before a click becomes a drag
+    pub drag_threshold: f32,
+    /// Keyboard scrolling speed, in pixels per second
+    pub scroll_speed: f32,
+    /// Zoom change per mouse wheel line or zoom key press
+    pub zoom_step: f32,
+    /// Smallest projection scale, i.e. the closest zoom
+    pub min_scale: f32,
+    /// Largest projection scale, i.e. the farthest zoom
+    pub max_scale: f32,
+    /// Space in pixels kept around the board when zooming to fit
+    pub fit_margin: f32,
+}
+
+impl Default for CameraOptions {
+    fn default() -> Self {
+        Self {
+            drag_threshold: 5.,
+            scroll_speed: 600.,
+            zoom_step: 0.1,
+            min_scale: 0.25,
+            max_scale: 16.,
+            fit_margin: 50.,
+        }
+    }
+}
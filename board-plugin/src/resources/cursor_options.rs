@@ -0,0 +1,7 @@
+/// Behaviour of the `TileCursor`, moved with the keys and gamepad buttons of the `InputMap`.
+/// The `BoardPlugin` inserts it with wrapping off, and it applies to the keyboard and gamepad cursors alike.
+#[derive(Debug, Clone, Default)]
+pub struct CursorOptions {
+    /// Does the cursor wrap around the board edges instead of stopping at them?
+    pub wrap: bool,
+}
@@ -0,0 +1,22 @@
+/// Gamepad controls of the `TileCursor` of every connected gamepad.
+/// The cursor moves with the buttons of the `InputMap` and the left stick.
+/// The `BoardPlugin` inserts the defaults, every gamepad shares the same stick threshold and repeat timing.
+#[derive(Debug, Clone)]
+pub struct GamepadOptions {
+    /// Left stick deflection, from 0 to 1, moving the cursor
+    pub stick_threshold: f32,
+    /// Seconds a direction is held before the cursor starts repeating moves
+    pub repeat_delay: f32,
+    /// Seconds between repeated moves
+    pub repeat_interval: f32,
+}
+
+impl Default for GamepadOptions {
+    fn default() -> Self {
+        Self {
+            stick_threshold: 0.5,
+            repeat_delay: 0.4,
+            repeat_interval: 0.1,
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, io, path::Path};
+
+/// Player action bound to inputs by the `InputMap`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    /// Uncovers a tile, or chords it if already uncovered
+    Uncover,
+    Mark,
+    Chord,
+    /// Moves the tile cursor up
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Undoes the last move, see `BoardHistory`
+    Undo,
+    Redo,
+    /// Asks for a hint, see `HintEvent`
+    Hint,
+    /// Mouse button dragging the camera around.
+    /// Clicks of this button act on the board when released, unless the cursor was dragged,
+    /// so its bindings are shared with the other actions, see `InputMap::bind`.
+    Pan,
+    /// Held to scroll the camera with the keys moving the tile cursor
+    Scroll,
+    ZoomIn,
+    ZoomOut,
+    /// Zooms out to fit the whole board in the window
+    Fit,
+    /// Starts a new game, handled by the game
+    Restart,
+    /// Clears the board, handled by the game
+    Clear,
+    /// Pauses or resumes the game, handled by the game
+    Pause,
+    /// Plays the next action of a replay played step by step, handled by the game
+    StepReplay,
+}
+
+/// Mouse button, key or gamepad button triggering an `InputAction`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum InputBinding {
+    Mouse(MouseButton),
+    Key(KeyCode),
+    Gamepad(GamepadButtonType),
+}
+
+/// Inputs bound to every `InputAction`, read by the board input systems.
+/// Mouse bindings act on the tile under the mouse cursor, keys and gamepad buttons on their `TileCursor`.
+/// The `BoardPlugin` inserts the defaults, games may replace or rebind it at any time, e.g. from a settings menu.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+    pub bindings: BTreeMap<InputAction, Vec<InputBinding>>,
+}
+
+/// Error raised when saving or loading an input map
+#[derive(Debug)]
+pub enum InputMapError {
+    #[cfg(not(target_arch = "wasm32"))]
+    Io(io::Error),
+    Ron(ron::Error),
+}
+
+impl Display for InputMapError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Io(error) => write!(f, "Failed to access input map file: {}", error),
+            Self::Ron(error) => write!(f, "Invalid input map: {}", error),
+        }
+    }
+}
+
+impl Error for InputMapError {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<io::Error> for InputMapError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ron::Error> for InputMapError {
+    fn from(error: ron::Error) -> Self {
+        Self::Ron(error)
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use InputBinding::{Gamepad, Key, Mouse};
+        let bindings = [
+            (
+                InputAction::Uncover,
+                vec![
+                    Mouse(MouseButton::Left),
+                    Key(KeyCode::Space),
+                    Key(KeyCode::Return),
+                    Gamepad(GamepadButtonType::South),
+                ],
+            ),
+            (
+                InputAction::Mark,
+                vec![
+                    Mouse(MouseButton::Right),
+                    Key(KeyCode::E),
+                    Gamepad(GamepadButtonType::East),
+                ],
+            ),
+            (
+                InputAction::Chord,
+                vec![
+                    Mouse(MouseButton::Middle),
+                    Key(KeyCode::Q),
+                    Gamepad(GamepadButtonType::West),
+                ],
+            ),
+            (
+                InputAction::Up,
+                vec![
+                    Key(KeyCode::Up),
+                    Key(KeyCode::W),
+                    Gamepad(GamepadButtonType::DPadUp),
+                ],
+            ),
+            (
+                InputAction::Down,
+                vec![
+                    Key(KeyCode::Down),
+                    Key(KeyCode::S),
+                    Gamepad(GamepadButtonType::DPadDown),
+                ],
+            ),
+            (
+                InputAction::Left,
+                vec![
+                    Key(KeyCode::Left),
+                    Key(KeyCode::A),
+                    Gamepad(GamepadButtonType::DPadLeft),
+                ],
+            ),
+            (
+                InputAction::Right,
+                vec![
+                    Key(KeyCode::Right),
+                    Key(KeyCode::D),
+                    Gamepad(GamepadButtonType::DPadRight),
+                ],
+            ),
+            (
+                InputAction::Undo,
+                vec![Key(KeyCode::Z), Gamepad(GamepadButtonType::LeftTrigger)],
+            ),
+            (
+                InputAction::Redo,
+                vec![Key(KeyCode::Y), Gamepad(GamepadButtonType::RightTrigger)],
+            ),
+            (
+                InputAction::Hint,
+                vec![Key(KeyCode::H), Gamepad(GamepadButtonType::North)],
+            ),
+            (InputAction::Pan, vec![Mouse(MouseButton::Left)]),
+            (
+                InputAction::Scroll,
+                vec![Key(KeyCode::LShift), Key(KeyCode::RShift)],
+            ),
+            (
+                InputAction::ZoomIn,
+                vec![Key(KeyCode::Equals), Key(KeyCode::NumpadAdd)],
+            ),
+            (
+                InputAction::ZoomOut,
+                vec![Key(KeyCode::Minus), Key(KeyCode::NumpadSubtract)],
+            ),
+            (InputAction::Fit, vec![Key(KeyCode::F)]),
+            (
+                InputAction::Restart,
+                vec![Key(KeyCode::G), Gamepad(GamepadButtonType::Select)],
+            ),
+            (InputAction::Clear, vec![Key(KeyCode::C)]),
+            (
+                InputAction::Pause,
+                vec![Key(KeyCode::Escape), Gamepad(GamepadButtonType::Start)],
+            ),
+            (InputAction::StepReplay, vec![Key(KeyCode::N)]),
+        ];
+        Self {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+impl InputMap {
+    /// Inputs bound to `action`
+    pub fn bindings(&self, action: InputAction) -> &[InputBinding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Binds `binding` to `action`, removing it from any other action but `InputAction::Pan`.
+    /// Pan bindings are kept apart and may also be bound to another action,
+    /// like the left mouse button dragging the camera and uncovering tiles by default.
+    pub fn bind(&mut self, action: InputAction, binding: InputBinding) {
+        let is_pan = |action| action == InputAction::Pan;
+        for (&bound_action, bindings) in self.bindings.iter_mut() {
+            if is_pan(bound_action) == is_pan(action) {
+                bindings.retain(|&bound| bound != binding);
+            }
+        }
+        self.bindings.entry(action).or_default().push(binding);
+    }
+
+    /// Removes `binding` from `action`
+    pub fn unbind(&mut self, action: InputAction, binding: InputBinding) {
+        if let Some(bindings) = self.bindings.get_mut(&action) {
+            bindings.retain(|&bound| bound != binding);
+        }
+    }
+
+    /// Is `binding` bound to `action`?
+    pub fn is_bound(&self, action: InputAction, binding: InputBinding) -> bool {
+        self.bindings(action).contains(&binding)
+    }
+
+    pub fn keys(&self, action: InputAction) -> impl Iterator<Item = KeyCode> + '_ {
+        self.bindings(action)
+            .iter()
+            .filter_map(|binding| match binding {
+                InputBinding::Key(key) => Some(*key),
+                _ => None,
+            })
+    }
+
+    pub fn mouse_buttons(&self, action: InputAction) -> impl Iterator<Item = MouseButton> + '_ {
+        self.bindings(action)
+            .iter()
+            .filter_map(|binding| match binding {
+                InputBinding::Mouse(button) => Some(*button),
+                _ => None,
+            })
+    }
+
+    pub fn gamepad_buttons(
+        &self,
+        action: InputAction,
+    ) -> impl Iterator<Item = GamepadButtonType> + '_ {
+        self.bindings(action)
+            .iter()
+            .filter_map(|binding| match binding {
+                InputBinding::Gamepad(button) => Some(*button),
+                _ => None,
+            })
+    }
+
+    /// Was any input bound to `action` just pressed, on any gamepad?
+    pub fn just_pressed(
+        &self,
+        action: InputAction,
+        keys: &Input<KeyCode>,
+        mouse_buttons: &Input<MouseButton>,
+        gamepad_buttons: &Input<GamepadButton>,
+    ) -> bool {
+        self.bindings(action).iter().any(|binding| match *binding {
+            InputBinding::Key(key) => keys.just_pressed(key),
+            InputBinding::Mouse(button) => mouse_buttons.just_pressed(button),
+            InputBinding::Gamepad(button_type) => gamepad_buttons
+                .get_just_pressed()
+                .any(|button| button.1 == button_type),
+        })
+    }
+
+    pub fn to_ron(&self) -> Result<String, InputMapError> {
+        Ok(ron::ser::to_string_pretty(self, Default::default())?)
+    }
+
+    /// Reads an input map from RON.
+    /// Actions missing from it, such as actions added since it was saved, keep their default bindings.
+    pub fn from_ron(ron: &str) -> Result<Self, InputMapError> {
+        let mut input_map: Self = ron::from_str(ron)?;
+        for (action, bindings) in Self::default().bindings {
+            input_map.bindings.entry(action).or_insert(bindings);
+        }
+        Ok(input_map)
+    }
+
+    /// Writes the input map to a file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), InputMapError> {
+        fs::write(path, self.to_ron()?)?;
+        Ok(())
+    }
+
+    /// Reads an input map from a file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, InputMapError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rebinds_and_round_trips() {
+        let mut input_map = InputMap::default();
+        let binding = InputBinding::Key(KeyCode::Space);
+        input_map.bind(InputAction::Mark, binding);
+        assert!(input_map.is_bound(InputAction::Mark, binding));
+        assert!(!input_map.is_bound(InputAction::Uncover, binding));
+        input_map.unbind(InputAction::Mark, binding);
+        assert!(!input_map.is_bound(InputAction::Mark, binding));
+
+        let ron = input_map.to_ron().unwrap();
+        assert_eq!(InputMap::from_ron(&ron).unwrap(), input_map);
+
+        input_map.bindings.remove(&InputAction::Fit);
+        let ron = input_map.to_ron().unwrap();
+        let loaded = InputMap::from_ron(&ron).unwrap();
+        assert!(loaded.is_bound(InputAction::Fit, InputBinding::Key(KeyCode::F)));
+    }
+
+    #[test]
+    fn shares_pan_bindings() {
+        let mut input_map = InputMap::default();
+        let left = InputBinding::Mouse(MouseButton::Left);
+        let right = InputBinding::Mouse(MouseButton::Right);
+        input_map.bind(InputAction::Mark, left);
+        assert!(input_map.is_bound(InputAction::Pan, left));
+        assert!(!input_map.is_bound(InputAction::Uncover, left));
+
+        input_map.bind(InputAction::Pan, right);
+        input_map.bind(InputAction::Pan, right);
+        assert!(input_map.is_bound(InputAction::Mark, right));
+        assert_eq!(input_map.bindings(InputAction::Pan), &[left, right]);
+    }
+}
@@ -1,9 +1,29 @@
 pub use board::*;
+pub use board_history::*;
 pub use board_options::*;
+pub use board_snapshot::*;
+pub use board_stats::*;
+pub use camera_options::*;
+pub use cursor_options::*;
+pub use gamepad_options::*;
+pub use input_map::*;
+pub use replay::*;
 pub use tile::*;
 pub use tile_map::*;
+pub use tile_map_format::*;
+pub use touch_options::*;
 
 mod board;
+mod board_history;
 mod board_options;
+mod board_snapshot;
+mod board_stats;
+mod camera_options;
+mod cursor_options;
+mod gamepad_options;
+mod input_map;
+mod replay;
 mod tile;
 mod tile_map;
+mod tile_map_format;
+mod touch_options;
@@ -0,0 +1,291 @@
+use crate::components::Coordinates;
+use crate::resources::BoardOptions;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, io, path::Path};
+
+/// Version of the replay file format, bumped on every incompatible change
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Frames to wait after playing an action, so it is fully handled before the next one
+const SETTLE_FRAMES: u8 = 2;
+
+/// Player action on the board
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ReplayAction {
+    Trigger(Coordinates),
+    Mark(Coordinates),
+    Chord(Coordinates),
+    Undo,
+    Redo,
+    /// Asks for a hint, the provably safe tiles it uncovers are not player actions
+    Hint,
+}
+
+/// Action with the time it was played at, since the board was created
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayEntry {
+    pub elapsed: Duration,
+    pub action: ReplayAction,
+}
+
+/// Recorded game, reproducible from its seed and options.
+///
+/// Boards built from a `BoardSource::Puzzle` are neither recorded nor played back,
+/// as the puzzle layout is not part of the options.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub options: BoardOptions,
+    pub actions: Vec<ReplayEntry>,
+}
+
+/// Error raised when saving or loading a replay
+#[derive(Debug)]
+pub enum ReplayError {
+    #[cfg(not(target_arch = "wasm32"))]
+    Io(io::Error),
+    Ron(ron::Error),
+    UnsupportedVersion {
+        found: u32,
+    },
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Io(error) => write!(f, "Failed to access replay file: {}", error),
+            Self::Ron(error) => write!(f, "Invalid replay: {}", error),
+            Self::UnsupportedVersion { found } => write!(
+                f,
+                "Unsupported replay version {}, expected {}",
+                found, REPLAY_VERSION
+            ),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<io::Error> for ReplayError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ron::Error> for ReplayError {
+    fn from(error: ron::Error) -> Self {
+        Self::Ron(error)
+    }
+}
+
+impl Replay {
+    pub fn new(seed: u64, options: BoardOptions) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            seed,
+            options,
+            actions: Vec::new(),
+        }
+    }
+
+    /// Options generating the recorded board
+    pub fn board_options(&self) -> BoardOptions {
+        BoardOptions {
+            seed: Some(self.seed),
+            ..self.options.clone()
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, ReplayError> {
+        Ok(ron::to_string(self)?)
+    }
+
+    pub fn from_ron(ron: &str) -> Result<Self, ReplayError> {
+        let replay: Self = ron::from_str(ron)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion {
+                found: replay.version,
+            });
+        }
+        Ok(replay)
+    }
+
+    /// Writes the replay to a file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        fs::write(path, self.to_ron()?)?;
+        Ok(())
+    }
+
+    /// Reads a replay from a file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+}
+
+/// Records the player actions of every new board while inserted as a resource.
+///
+/// Boards resumed from a `BoardSnapshot` are recorded from their resumed state,
+/// so their replay can not be played back.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayRecorder {
+    pub(crate) replay: Option<Replay>,
+    pub(crate) elapsed: Duration,
+}
+
+impl ReplayRecorder {
+    /// Replay of the current board, available once the board is created
+    pub fn replay(&self) -> Option<&Replay> {
+        self.replay.as_ref()
+    }
+}
+
+/// Playback speed of a `ReplayPlayer`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PlaybackSpeed {
+    /// Actions are played at their recorded time
+    RealTime,
+    /// Recorded times are divided by the given factor
+    FastForward(f32),
+    /// Actions are played one by one with `ReplayPlayer::step`
+    Step,
+}
+
+/// Plays a replay back on the board while inserted as a resource, ignoring player input.
+///
+/// Insert the `Replay::board_options` as `BoardOptions` so the recorded board is generated.
+#[derive(Debug, Clone)]
+pub struct ReplayPlayer {
+    pub replay: Replay,
+    pub speed: PlaybackSpeed,
+    elapsed: Duration,
+    next: usize,
+    steps: usize,
+    settle_frames: u8,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay, speed: PlaybackSpeed) -> Self {
+        Self {
+            replay,
+            speed,
+            elapsed: Duration::ZERO,
+            next: 0,
+            steps: 0,
+            settle_frames: 0,
+        }
+    }
+
+    /// Allows the next action to be played in `PlaybackSpeed::Step` mode
+    pub fn step(&mut self) {
+        self.steps += 1;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.actions.len()
+    }
+
+    /// Advances the playback by a frame, returning the action to play if any.
+    /// At most one action is played per frame and only when `settled`,
+    /// i.e. when the previous action is fully handled, so the game unfolds like it was recorded.
+    pub(crate) fn advance(&mut self, delta: Duration, settled: bool) -> Option<ReplayAction> {
+        match self.speed {
+            PlaybackSpeed::RealTime => self.elapsed += delta,
+            PlaybackSpeed::FastForward(factor) => self.elapsed += delta.mul_f32(factor),
+            PlaybackSpeed::Step => (),
+        }
+        if self.settle_frames > 0 {
+            self.settle_frames -= 1;
+            return None;
+        }
+        let entry = self.replay.actions.get(self.next)?;
+        let due = match self.speed {
+            PlaybackSpeed::Step => self.steps > 0,
+            _ => entry.elapsed <= self.elapsed,
+        };
+        if !due || !settled {
+            return None;
+        }
+        self.steps = self.steps.saturating_sub(1);
+        self.next += 1;
+        self.settle_frames = SETTLE_FRAMES;
+        Some(entry.action)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn replay() -> Replay {
+        let mut replay = Replay::new(7, BoardOptions::default());
+        replay.actions = vec![
+            ReplayEntry {
+                elapsed: Duration::from_secs(1),
+                action: ReplayAction::Trigger(Coordinates { x: 1, y: 2 }),
+            },
+            ReplayEntry {
+                elapsed: Duration::from_secs(2),
+                action: ReplayAction::Mark(Coordinates { x: 3, y: 0 }),
+            },
+        ];
+        replay
+    }
+
+    #[test]
+    fn survives_ron_round_trip() {
+        let replay = replay();
+        let loaded = Replay::from_ron(&replay.to_ron().unwrap()).unwrap();
+        assert_eq!(loaded.seed, 7);
+        assert_eq!(loaded.actions, replay.actions);
+        assert_eq!(loaded.board_options().seed, Some(7));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut replay = replay();
+        replay.version = REPLAY_VERSION + 1;
+        let error = Replay::from_ron(&replay.to_ron().unwrap()).unwrap_err();
+        assert!(matches!(error, ReplayError::UnsupportedVersion { .. }));
+    }
+
+    #[test]
+    fn plays_actions_at_their_time() {
+        let mut player = ReplayPlayer::new(replay(), PlaybackSpeed::FastForward(2.));
+        let frame = Duration::from_millis(125);
+        // A quarter second of the replay per frame
+        let played: Vec<_> = (1..=10)
+            .filter_map(|index| player.advance(frame, true).map(|_| index))
+            .collect();
+        assert_eq!(played, vec![4, 8]);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn waits_for_previous_action_to_settle() {
+        let mut player = ReplayPlayer::new(replay(), PlaybackSpeed::RealTime);
+        assert_eq!(player.advance(Duration::from_secs(5), false), None);
+        assert!(player.advance(Duration::ZERO, true).is_some());
+    }
+
+    #[test]
+    fn steps_one_action_at_a_time() {
+        let mut player = ReplayPlayer::new(replay(), PlaybackSpeed::Step);
+        assert_eq!(player.advance(Duration::from_secs(10), true), None);
+        player.step();
+        assert!(player.advance(Duration::ZERO, true).is_some());
+        for _ in 0..SETTLE_FRAMES {
+            player.advance(Duration::ZERO, true);
+        }
+        assert_eq!(player.advance(Duration::ZERO, true), None);
+        assert!(!player.is_finished());
+    }
+}
@@ -1,8 +1,9 @@
 #[cfg(feature = "debug")]
 use colored::Colorize;
+use serde::{Deserialize, Serialize};
 
 /// Minesweeper tile
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum Tile {
     /// Empty tile
     Empty,
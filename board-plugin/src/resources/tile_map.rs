@@ -1,14 +1,52 @@
-use crate::{components::Coordinates, resources::tile::Tile};
-use rand::{thread_rng, Rng};
-use std::ops::{Deref, DerefMut};
+use crate::{
+    components::Coordinates,
+    resources::{tile::Tile, Generator},
+    solver,
+};
+use bevy::log;
+use bevy::utils::HashSet;
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// Error raised when no acceptable mine layout could be generated
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GenerationError {
+    /// No layout solvable without guessing was found, usually because the mine density is too high
+    NoGuessLayoutNotFound { attempts: u32 },
+    /// No-guess generation was requested without a start the player gets to see,
+    /// i.e. neither `safe_start` nor `first_click_safety` is set
+    NoGuessWithoutStart,
+}
+
+impl Display for GenerationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoGuessLayoutNotFound { attempts } => write!(
+                f,
+                "Failed to generate a board solvable without guessing after {} attempts",
+                attempts
+            ),
+            Self::NoGuessWithoutStart => write!(
+                f,
+                "No-guess generation needs a safe start or first click safety, placing mines at random"
+            ),
+        }
+    }
+}
+
+impl Error for GenerationError {}
 
 /// Base tile map
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TileMap {
     pub mine_count: u16,
     pub width: u16,
     pub height: u16,
-    map: Vec<Vec<Tile>>,
+    /// Tiles row by row from the bottom left, in a single buffer
+    tiles: Vec<Tile>,
 }
 
 impl TileMap {
@@ -18,36 +56,145 @@ impl TileMap {
             mine_count: 0,
             width,
             height,
-            map: vec![vec![Tile::Empty; width as usize]; height as usize],
+            tiles: vec![Tile::Empty; width as usize * height as usize],
+        }
+    }
+
+    /// Generate a tile map with mines at the given coordinates.
+    /// Coordinates outside of the map, duplicates and mines past `u16::MAX` are ignored.
+    pub fn from_mines(
+        width: u16,
+        height: u16,
+        mines: impl IntoIterator<Item = Coordinates>,
+    ) -> Self {
+        let mut tile_map = Self::empty(width, height);
+        for coordinates in mines {
+            if tile_map
+                .get(coordinates)
+                .map_or(true, |tile| *tile == Tile::Mine)
+            {
+                continue;
+            }
+            tile_map.mine_count = match tile_map.mine_count.checked_add(1) {
+                Some(mine_count) => mine_count,
+                None => break,
+            };
+            if let Some(tile) = tile_map.get_mut(coordinates) {
+                *tile = Tile::Mine;
+            }
         }
+        tile_map.update_neighbor_counts();
+        tile_map
     }
 
-    /// Places mine and neighbor tiles
+    /// Places mine and neighbor tiles at random
     pub fn place_mines(&mut self, mine_count: u16) -> &Self {
+        self.place_mines_with_rng(mine_count, &mut thread_rng())
+    }
+
+    /// Places mine and neighbor tiles deterministically.
+    /// The same seed, map size and mine count always yield the same map, on every platform.
+    pub fn place_mines_seeded(&mut self, mine_count: u16, seed: u64) -> &Self {
+        self.place_mines_with_rng(mine_count, &mut Self::seeded_rng(seed))
+    }
+
+    /// Places mine and neighbor tiles using the given random number generator
+    pub fn place_mines_with_rng<R: Rng + ?Sized>(&mut self, mine_count: u16, rng: &mut R) -> &Self {
+        self.place_mines_excluding(mine_count, &[], rng)
+    }
+
+    /// Places mine and neighbor tiles, keeping the `excluded` coordinates free of mines.
+    /// If there are not enough free tiles left, the mine count is reduced accordingly and a warning is logged,
+    /// see `BoardOptions::placeable_mine_count` to pick a mine count that fits beforehand.
+    pub fn place_mines_excluding<R: Rng + ?Sized>(
+        &mut self,
+        mine_count: u16,
+        excluded: &[Coordinates],
+        rng: &mut R,
+    ) -> &Self {
+        let excluded: HashSet<_> = excluded
+            .iter()
+            .copied()
+            .filter(|coordinates| !self.is_out_of_bounds(*coordinates))
+            .collect();
+        let free_tiles =
+            (self.width as usize * self.height as usize).saturating_sub(excluded.len());
+        let placeable_count = mine_count.min(free_tiles.min(u16::MAX as usize) as u16);
+        if placeable_count < mine_count {
+            log::warn!(
+                "Only {} free tiles left for {} mines, placing {} mines",
+                free_tiles,
+                mine_count,
+                placeable_count
+            );
+        }
+        let mine_count = placeable_count;
+
         self.mine_count = mine_count;
         let mut remaining_mines = mine_count;
-        let mut rng = thread_rng();
         while remaining_mines > 0 {
-            let x = rng.gen_range(0..self.width) as usize;
-            let y = rng.gen_range(0..self.height) as usize;
+            let x = rng.gen_range(0..self.width);
+            let y = rng.gen_range(0..self.height);
+            if excluded.contains(&Coordinates { x, y }) {
+                continue;
+            }
 
-            if self.map[y][x] == Tile::Empty {
-                self.map[y][x] = Tile::Mine;
-                remaining_mines -= 1;
+            if let Some(tile) = self.get_mut(Coordinates { x, y }) {
+                if *tile == Tile::Empty {
+                    *tile = Tile::Mine;
+                    remaining_mines -= 1;
+                }
             }
         }
 
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let coordinates = Coordinates { x, y };
-                let mine_count = self.neighbor_count_at(coordinates);
-                if mine_count > 0 {
-                    self[y as usize][x as usize] = Tile::Neighbor(mine_count);
+        self.update_neighbor_counts();
+        self
+    }
+
+    /// Places mines according to the generator, keeping the `excluded` coordinates free of mines.
+    /// `start` is the first tile the player uncovers, defaulting to the first empty tile.
+    /// On error, the map is still filled with the last attempted layout.
+    pub fn generate<R: Rng + ?Sized>(
+        &mut self,
+        mine_count: u16,
+        generator: Generator,
+        excluded: &[Coordinates],
+        start: Option<Coordinates>,
+        rng: &mut R,
+    ) -> Result<(), GenerationError> {
+        let max_attempts = match generator {
+            Generator::Random => {
+                self.place_mines_excluding(mine_count, excluded, rng);
+                return Ok(());
+            }
+            Generator::NoGuess { max_attempts } => max_attempts.max(1),
+        };
+
+        for _ in 0..max_attempts {
+            *self = Self::empty(self.width, self.height);
+            self.place_mines_excluding(mine_count, excluded, rng);
+            if let Some(start) = start.or_else(|| self.first_empty_tile()) {
+                if solver::is_solvable_from(self, start) {
+                    return Ok(());
                 }
             }
         }
+        Err(GenerationError::NoGuessLayoutNotFound {
+            attempts: max_attempts,
+        })
+    }
 
-        self
+    /// First empty tile, row by row from the bottom left
+    pub fn first_empty_tile(&self) -> Option<Coordinates> {
+        self.iter_with_coordinates()
+            .find(|(_, tile)| **tile == Tile::Empty)
+            .map(|(coordinates, _)| coordinates)
+    }
+
+    /// Portable random number generator used for seeded generation.
+    /// Unlike `StdRng`, its output is guaranteed to be stable across platforms, including wasm32.
+    pub fn seeded_rng(seed: u64) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(seed)
     }
 
     #[cfg(feature = "debug")]
@@ -61,7 +208,7 @@ impl TileMap {
         buffer.push_str(&line);
         buffer.push('\n');
 
-        for line in self.iter().rev() {
+        for line in self.rows().rev() {
             buffer.push('|');
             for tile in line {
                 buffer.push_str(&tile.console_output());
@@ -80,12 +227,35 @@ impl TileMap {
             .map(move |offset| coordinates + offset)
     }
 
+    /// Coordinates of the square of the given radius centered on `coordinates`, including the center.
+    /// Coordinates outside of the map are skipped.
+    pub fn area_at(&self, coordinates: Coordinates, radius: u16) -> Vec<Coordinates> {
+        let (min_x, min_y) = (
+            coordinates.x.saturating_sub(radius),
+            coordinates.y.saturating_sub(radius),
+        );
+        let (max_x, max_y) = (
+            coordinates
+                .x
+                .saturating_add(radius)
+                .min(self.width.saturating_sub(1)),
+            coordinates
+                .y
+                .saturating_add(radius)
+                .min(self.height.saturating_sub(1)),
+        );
+        (min_y..=max_y)
+            .flat_map(|y| (min_x..=max_x).map(move |x| Coordinates { x, y }))
+            .collect()
+    }
+
+    #[inline]
     pub fn is_mine_at(&self, coordinates: Coordinates) -> bool {
         if self.is_out_of_bounds(coordinates) {
             return false;
         }
 
-        self.map[coordinates.y as usize][coordinates.x as usize] == Tile::Mine
+        self.get(coordinates) == Some(&Tile::Mine)
     }
 
     /// Counts the number of mines surrounding the given coordinates
@@ -100,22 +270,68 @@ impl TileMap {
             .count() as u8
     }
 
-    fn is_out_of_bounds(&self, coordinates: Coordinates) -> bool {
-        coordinates.x >= self.width || coordinates.y >= self.height
+    /// Turns every empty tile next to a mine into a neighbor tile
+    fn update_neighbor_counts(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let coordinates = Coordinates { x, y };
+                let mine_count = self.neighbor_count_at(coordinates);
+                match self.get_mut(coordinates) {
+                    Some(tile) if mine_count > 0 => *tile = Tile::Neighbor(mine_count),
+                    _ => (),
+                }
+            }
+        }
     }
-}
 
-impl Deref for TileMap {
-    type Target = Vec<Vec<Tile>>;
+    /// Tile at the given coordinates, `None` if out of bounds
+    #[inline]
+    pub fn get(&self, coordinates: Coordinates) -> Option<&Tile> {
+        self.tiles.get(self.index_of(coordinates)?)
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.map
+    #[inline]
+    pub fn get_mut(&mut self, coordinates: Coordinates) -> Option<&mut Tile> {
+        let index = self.index_of(coordinates)?;
+        self.tiles.get_mut(index)
+    }
+
+    /// Every tile with its coordinates, row by row from the bottom left
+    pub fn iter_with_coordinates(&self) -> impl Iterator<Item = (Coordinates, &Tile)> {
+        self.rows().enumerate().flat_map(|(y, row)| {
+            row.iter().enumerate().map(move |(x, tile)| {
+                let coordinates = Coordinates {
+                    x: x as u16,
+                    y: y as u16,
+                };
+                (coordinates, tile)
+            })
+        })
+    }
+
+    /// Rows of tiles from the bottom
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[Tile]> {
+        // `max(1)` keeps empty maps from panicking, they have no tiles anyway
+        self.tiles.chunks_exact(self.width.max(1) as usize)
+    }
+
+    /// Tiles stored in the buffer, which only differs from `width * height` in corrupted data
+    pub(crate) fn stored_tile_count(&self) -> usize {
+        self.tiles.len()
     }
-}
 
-impl DerefMut for TileMap {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.map
+    /// Index of a tile row by row from the bottom left, `None` out of the map
+    #[inline]
+    pub(crate) fn index_of(&self, coordinates: Coordinates) -> Option<usize> {
+        if self.is_out_of_bounds(coordinates) {
+            return None;
+        }
+        Some(coordinates.y as usize * self.width as usize + coordinates.x as usize)
+    }
+
+    #[inline]
+    fn is_out_of_bounds(&self, coordinates: Coordinates) -> bool {
+        coordinates.x >= self.width || coordinates.y >= self.height
     }
 }
 
@@ -147,3 +363,107 @@ const SQUARE_COORDINATES: [(i8, i8); 8] = [
     // Top right
     (1, 1),
 ];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mine_coordinates(tile_map: &TileMap) -> Vec<Coordinates> {
+        (0..tile_map.height)
+            .flat_map(|y| (0..tile_map.width).map(move |x| Coordinates { x, y }))
+            .filter(|coordinates| tile_map.is_mine_at(*coordinates))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_generates_same_map() {
+        let mut first = TileMap::empty(20, 15);
+        first.place_mines_seeded(40, 1337);
+        let mut second = TileMap::empty(20, 15);
+        second.place_mines_seeded(40, 1337);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn different_seeds_generate_different_maps() {
+        let mut first = TileMap::empty(20, 15);
+        first.place_mines_seeded(40, 1);
+        let mut second = TileMap::empty(20, 15);
+        second.place_mines_seeded(40, 2);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn places_requested_mine_count() {
+        let mut tile_map = TileMap::empty(10, 10);
+        tile_map.place_mines_seeded(25, 42);
+        assert_eq!(mine_coordinates(&tile_map).len(), 25);
+    }
+
+    #[test]
+    fn keeps_excluded_area_free_of_mines() {
+        let mut tile_map = TileMap::empty(5, 5);
+        let excluded = tile_map.area_at(Coordinates { x: 0, y: 0 }, 1);
+        assert_eq!(excluded.len(), 4);
+        tile_map.place_mines_excluding(21, &excluded, &mut TileMap::seeded_rng(7));
+        assert_eq!(tile_map.mine_count, 21);
+        assert!(excluded
+            .iter()
+            .all(|coordinates| !tile_map.is_mine_at(*coordinates)));
+        assert_eq!(tile_map.neighbor_count_at(Coordinates { x: 0, y: 0 }), 0);
+    }
+
+    #[test]
+    fn reduces_mine_count_when_area_is_too_large() {
+        let mut tile_map = TileMap::empty(3, 3);
+        let excluded = tile_map.area_at(Coordinates { x: 1, y: 1 }, 2);
+        assert_eq!(excluded.len(), 9);
+        tile_map.place_mines_excluding(5, &excluded, &mut TileMap::seeded_rng(7));
+        assert_eq!(tile_map.mine_count, 0);
+    }
+
+    #[test]
+    fn generates_layouts_without_guessing() {
+        let mut tile_map = TileMap::empty(9, 9);
+        let generator = Generator::NoGuess { max_attempts: 100 };
+        let start = Coordinates { x: 4, y: 4 };
+        let excluded = tile_map.area_at(start, 1);
+        let result = tile_map.generate(
+            10,
+            generator,
+            &excluded,
+            Some(start),
+            &mut TileMap::seeded_rng(3),
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!(mine_coordinates(&tile_map).len(), 10);
+        assert!(solver::is_solvable_from(&tile_map, start));
+    }
+
+    #[test]
+    fn reports_impossible_no_guess_layouts() {
+        let mut tile_map = TileMap::empty(5, 5);
+        let generator = Generator::NoGuess { max_attempts: 5 };
+        let result = tile_map.generate(20, generator, &[], None, &mut TileMap::seeded_rng(3));
+        assert_eq!(
+            result,
+            Err(GenerationError::NoGuessLayoutNotFound { attempts: 5 })
+        );
+        assert_eq!(mine_coordinates(&tile_map).len(), 20);
+    }
+
+    #[test]
+    fn seeded_layout_is_stable() {
+        let mut tile_map = TileMap::empty(5, 5);
+        tile_map.place_mines_seeded(3, 42);
+        // Guards against changes to the generator breaking shared seeds
+        assert_eq!(
+            mine_coordinates(&tile_map),
+            vec![
+                Coordinates { x: 3, y: 2 },
+                Coordinates { x: 1, y: 3 },
+                Coordinates { x: 0, y: 4 },
+            ]
+        );
+    }
+}
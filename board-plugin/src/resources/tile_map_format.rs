@@ -0,0 +1,269 @@
+//! Board layout formats shared with other minesweeper clones and analysis tools.
+//!
+//! Both formats list rows from the top of the board, while `TileMap` rows start at the bottom.
+
+use crate::components::Coordinates;
+use crate::resources::{Tile, TileMap};
+use fixedbitset::FixedBitSet;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// Error raised when importing or exporting a board layout
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FormatError {
+    /// The data ends before the announced mines
+    Truncated,
+    /// The data goes on after the announced mines
+    TrailingData,
+    /// The board has no tiles
+    EmptyBoard,
+    /// The board is too large for the format
+    BoardTooLarge { width: u16, height: u16 },
+    /// The board holds more mines than a `TileMap` can count
+    TooManyMines,
+    /// A mine is listed outside of the board
+    MineOutOfBounds { x: u16, y: u16 },
+    /// A mine is listed twice
+    DuplicateMine { x: u16, y: u16 },
+    /// A text grid row is not as long as the first one
+    UnevenRow { line: usize },
+    /// A text grid holds an unknown tile character
+    InvalidCharacter { line: usize, character: char },
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "Board data ends before its last mine"),
+            Self::TrailingData => write!(f, "Board data goes on after its last mine"),
+            Self::EmptyBoard => write!(f, "Board has no tiles"),
+            Self::BoardTooLarge { width, height } => {
+                write!(
+                    f,
+                    "Board of {}x{} is too large for the format",
+                    width, height
+                )
+            }
+            Self::TooManyMines => write!(f, "Board has more than {} mines", u16::MAX),
+            Self::MineOutOfBounds { x, y } => {
+                write!(f, "Mine at ({}, {}) is outside of the board", x, y)
+            }
+            Self::DuplicateMine { x, y } => write!(f, "Mine at ({}, {}) is listed twice", x, y),
+            Self::UnevenRow { line } => {
+                write!(f, "Row on line {} does not match the board width", line)
+            }
+            Self::InvalidCharacter { line, character } => {
+                write!(f, "Invalid tile {:?} on line {}", character, line)
+            }
+        }
+    }
+}
+
+impl Error for FormatError {}
+
+impl TileMap {
+    /// Reads a board in the binary MBF format of Minesweeper Arbiter and the Vienna tools:
+    /// width and height as bytes, the mine count as big endian `u16`, then the `x` and `y` byte of every mine.
+    pub fn from_mbf(bytes: &[u8]) -> Result<Self, FormatError> {
+        let (header, mines) = match bytes {
+            [width, height, count_high, count_low, mines @ ..] => (
+                (
+                    *width,
+                    *height,
+                    u16::from_be_bytes([*count_high, *count_low]),
+                ),
+                mines,
+            ),
+            _ => return Err(FormatError::Truncated),
+        };
+        let (width, height, mine_count) = (header.0 as u16, header.1 as u16, header.2);
+        if width == 0 || height == 0 {
+            return Err(FormatError::EmptyBoard);
+        }
+        match mines.len().cmp(&(mine_count as usize * 2)) {
+            Ordering::Less => return Err(FormatError::Truncated),
+            Ordering::Greater => return Err(FormatError::TrailingData),
+            Ordering::Equal => (),
+        }
+
+        let mut coordinates = Vec::with_capacity(mine_count as usize);
+        let mut seen = FixedBitSet::with_capacity(width as usize * height as usize);
+        for mine in mines.chunks_exact(2) {
+            let (x, y) = (mine[0] as u16, mine[1] as u16);
+            if x >= width || y >= height {
+                return Err(FormatError::MineOutOfBounds { x, y });
+            }
+            let mine = Coordinates {
+                x,
+                y: height - 1 - y,
+            };
+            if seen.put(mine.y as usize * width as usize + mine.x as usize) {
+                return Err(FormatError::DuplicateMine { x, y });
+            }
+            coordinates.push(mine);
+        }
+        Ok(Self::from_mines(width, height, coordinates))
+    }
+
+    /// Writes the board in the binary MBF format, limited to boards of 255x255 tiles
+    pub fn to_mbf(&self) -> Result<Vec<u8>, FormatError> {
+        if self.width > u8::MAX as u16 || self.height > u8::MAX as u16 {
+            return Err(FormatError::BoardTooLarge {
+                width: self.width,
+                height: self.height,
+            });
+        }
+        let mines = self.mine_coordinates();
+        let mut bytes = Vec::with_capacity(4 + mines.len() * 2);
+        bytes.extend([self.width as u8, self.height as u8]);
+        bytes.extend((mines.len() as u16).to_be_bytes());
+        for mine in mines {
+            bytes.extend([mine.x as u8, (self.height - 1 - mine.y) as u8]);
+        }
+        Ok(bytes)
+    }
+
+    /// Reads a plain text grid, one line per row with `*` for mines.
+    /// Safe tiles may be written as `.`, `_` or their neighbor count, which is recomputed anyway.
+    /// Blank lines are skipped.
+    pub fn from_text(text: &str) -> Result<Self, FormatError> {
+        let mut rows = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            let row = line
+                .chars()
+                .map(|character| match character {
+                    '*' => Ok(true),
+                    '.' | '_' | '0'..='8' => Ok(false),
+                    _ => Err(FormatError::InvalidCharacter {
+                        line: index + 1,
+                        character,
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if rows
+                .first()
+                .map_or(false, |first: &Vec<bool>| first.len() != row.len())
+            {
+                return Err(FormatError::UnevenRow { line: index + 1 });
+            }
+            rows.push(row);
+        }
+
+        let width = rows.first().map_or(0, Vec::len);
+        let height = rows.len();
+        if width == 0 {
+            return Err(FormatError::EmptyBoard);
+        }
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(FormatError::BoardTooLarge {
+                width: width.min(u16::MAX as usize) as u16,
+                height: height.min(u16::MAX as usize) as u16,
+            });
+        }
+        let mine_count = rows.iter().flatten().filter(|is_mine| **is_mine).count();
+        if mine_count > u16::MAX as usize {
+            return Err(FormatError::TooManyMines);
+        }
+        let mines = rows.iter().rev().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, is_mine)| **is_mine)
+                .map(move |(x, _)| Coordinates {
+                    x: x as u16,
+                    y: y as u16,
+                })
+        });
+        Ok(Self::from_mines(width as u16, height as u16, mines))
+    }
+
+    /// Writes the board as a plain text grid, with `*` for mines, neighbor counts and `.` for empty tiles
+    pub fn to_text(&self) -> String {
+        let mut buffer = String::with_capacity((self.width as usize + 1) * self.height as usize);
+        for line in self.rows().rev() {
+            for tile in line {
+                match tile {
+                    Tile::Empty => buffer.push('.'),
+                    Tile::Mine => buffer.push('*'),
+                    Tile::Neighbor(count) => buffer.push_str(&count.to_string()),
+                }
+            }
+            buffer.push('\n');
+        }
+        buffer
+    }
+
+    /// Mine coordinates, row by row from the bottom left
+    fn mine_coordinates(&self) -> Vec<Coordinates> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Coordinates { x, y }))
+            .filter(|coordinates| self.is_mine_at(*coordinates))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const GRID: &str = "*..\n...\n.**\n.*.\n";
+
+    #[test]
+    fn reads_text_grid_from_the_top() {
+        let tile_map = TileMap::from_text(GRID).unwrap();
+        assert_eq!((tile_map.width, tile_map.height), (3, 4));
+        assert_eq!(tile_map.mine_count, 4);
+        assert!(tile_map.is_mine_at(Coordinates { x: 0, y: 3 }));
+        assert!(tile_map.is_mine_at(Coordinates { x: 1, y: 0 }));
+        assert_eq!(tile_map.to_text(), "*1.\n232\n2**\n2*3\n");
+        assert_eq!(TileMap::from_text(&tile_map.to_text()), Ok(tile_map));
+    }
+
+    #[test]
+    fn mbf_round_trip() {
+        let tile_map = TileMap::from_text(GRID).unwrap();
+        let bytes = tile_map.to_mbf().unwrap();
+        assert_eq!(bytes, vec![3, 4, 0, 4, 1, 3, 1, 2, 2, 2, 0, 0]);
+        assert_eq!(TileMap::from_mbf(&bytes), Ok(tile_map));
+    }
+
+    #[test]
+    fn rejects_invalid_layouts() {
+        assert_eq!(
+            TileMap::from_text("*.\n..x\n"),
+            Err(FormatError::InvalidCharacter {
+                line: 2,
+                character: 'x'
+            })
+        );
+        assert_eq!(
+            TileMap::from_text("*.\n...\n"),
+            Err(FormatError::UnevenRow { line: 2 })
+        );
+        assert_eq!(
+            TileMap::from_mbf(&[3, 4, 0, 2, 1, 1]),
+            Err(FormatError::Truncated)
+        );
+        assert_eq!(
+            TileMap::from_mbf(&[3, 4, 0, 1, 1, 1, 2]),
+            Err(FormatError::TrailingData)
+        );
+        assert_eq!(
+            TileMap::from_mbf(&[3, 4, 0, 1, 3, 0]),
+            Err(FormatError::MineOutOfBounds { x: 3, y: 0 })
+        );
+        assert_eq!(
+            TileMap::from_mbf(&[3, 4, 0, 2, 1, 2, 1, 2]),
+            Err(FormatError::DuplicateMine { x: 1, y: 2 })
+        );
+        let mined_rows = format!("{}\n", "*".repeat(256)).repeat(256);
+        assert_eq!(
+            TileMap::from_text(&mined_rows),
+            Err(FormatError::TooManyMines)
+        );
+    }
+}
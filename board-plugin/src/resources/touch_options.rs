@@ -0,0 +1,20 @@
+/// Touch controls of the board.
+/// Taps uncover tiles or chord uncovered ones, long presses mark tiles.
+/// The `BoardPlugin` inserts it with flag mode off, games toggle the mode through `ResMut<TouchOptions>`.
+#[derive(Debug, Clone)]
+pub struct TouchOptions {
+    /// Seconds a touch is held before it marks its tile
+    pub long_press: f32,
+    /// Swaps taps and long presses on covered tiles, so taps mark them.
+    /// Meant to be toggled by the game, e.g. with a button.
+    pub flag_mode: bool,
+}
+
+impl Default for TouchOptions {
+    fn default() -> Self {
+        Self {
+            long_press: 0.5,
+            flag_mode: false,
+        }
+    }
+}
@@ -0,0 +1,429 @@
+use crate::components::Coordinates;
+use crate::resources::TileMap;
+use bevy::utils::{HashMap, HashSet};
+use std::cmp::Ordering;
+
+/// Tiles the solver proved to be safe or mined
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deductions {
+    pub safe: HashSet<Coordinates>,
+    pub mines: HashSet<Coordinates>,
+}
+
+impl Deductions {
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.mines.is_empty()
+    }
+}
+
+/// Tile suggested to a stuck player
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Hint {
+    pub coordinates: Coordinates,
+    /// Estimated chance of the tile being a mine, 0 if it is provably safe
+    pub risk: f32,
+}
+
+/// A revealed neighbor count applied to the tiles still unknown around it
+#[derive(Debug, Clone)]
+struct Constraint {
+    /// Unknown tiles around the revealed tile, sorted
+    unknown: Vec<Coordinates>,
+    /// Mines among `unknown`
+    mines: u8,
+}
+
+impl Constraint {
+    fn without(&self, other: &Self) -> Vec<Coordinates> {
+        self.unknown
+            .iter()
+            .filter(|coordinates| other.unknown.binary_search(coordinates).is_err())
+            .copied()
+            .collect()
+    }
+}
+
+/// Logical minesweeper solver working only with what a player can see:
+/// revealed neighbor counts and tiles known to be mines.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    width: u16,
+    height: u16,
+    mine_count: u16,
+    revealed: HashMap<Coordinates, u8>,
+    mines: HashSet<Coordinates>,
+}
+
+impl Solver {
+    pub fn new(width: u16, height: u16, mine_count: u16) -> Self {
+        Self {
+            width,
+            height,
+            mine_count,
+            revealed: HashMap::default(),
+            mines: HashSet::default(),
+        }
+    }
+
+    /// Records an uncovered tile and its neighbor count
+    pub fn reveal(&mut self, coordinates: Coordinates, count: u8) {
+        self.revealed.insert(coordinates, count);
+    }
+
+    /// Records a tile known to be a mine
+    pub fn mark_mine(&mut self, coordinates: Coordinates) {
+        self.mines.insert(coordinates);
+    }
+
+    pub fn is_unknown(&self, coordinates: Coordinates) -> bool {
+        !self.revealed.contains_key(&coordinates) && !self.mines.contains(&coordinates)
+    }
+
+    /// Number of tiles that are neither revealed nor known mines
+    pub fn unknown_count(&self) -> usize {
+        self.width as usize * self.height as usize - self.revealed.len() - self.mines.len()
+    }
+
+    /// Finds every tile that is provably safe or mined,
+    /// using single point reasoning, overlapping constraint sets and the total mine count.
+    pub fn deduce(&self) -> Deductions {
+        let mut deductions = Deductions::default();
+        let constraints = self.constraints();
+
+        // Single point: a count is satisfied, or all of its unknown neighbors must be mines
+        for constraint in constraints.values() {
+            if constraint.mines == 0 {
+                deductions.safe.extend(constraint.unknown.iter().copied());
+            } else if constraint.mines as usize == constraint.unknown.len() {
+                deductions.mines.extend(constraint.unknown.iter().copied());
+            }
+        }
+
+        // Constraint sets: compare every count with the counts close enough to share unknown tiles
+        for (&coordinates, a) in constraints.iter() {
+            for other in self.square(coordinates, 2) {
+                let b = match constraints.get(&other) {
+                    Some(b) if other != coordinates => b,
+                    _ => continue,
+                };
+                let only_a = a.without(b);
+                let only_b = b.without(a);
+                if only_b.is_empty()
+                    || only_a.len() + only_b.len() == a.unknown.len() + b.unknown.len()
+                {
+                    // Identical or disjoint sets tell us nothing new
+                    continue;
+                }
+                let extra_mines = b.mines as i16 - a.mines as i16;
+                if only_a.is_empty() && extra_mines == 0 {
+                    // `a` is a subset of `b` holding all of its mines
+                    deductions.safe.extend(only_b);
+                } else if extra_mines == only_b.len() as i16 {
+                    // Every tile only `b` sees is a mine, so the shared tiles hold all mines of `a`
+                    deductions.mines.extend(only_b);
+                    deductions.safe.extend(only_a);
+                }
+            }
+        }
+
+        // Global mine count
+        let remaining_mines = self.remaining_mines();
+        let unknown_count = self.unknown_count();
+        if unknown_count > 0 && (remaining_mines == 0 || remaining_mines == unknown_count) {
+            let unknown = self.unknown_tiles();
+            if remaining_mines == 0 {
+                deductions.safe.extend(unknown);
+            } else {
+                deductions.mines.extend(unknown);
+            }
+        } else if remaining_mines == Self::disjoint_mines(&constraints) {
+            // The counts already account for every remaining mine, none is left for the other tiles
+            deductions
+                .safe
+                .extend(self.unconstrained_tiles(&constraints));
+        }
+
+        deductions
+    }
+
+    /// Finds a provably safe tile, taking the mines deduced along the way into account,
+    /// or else the tile least likely to be a mine. The `avoided` tiles are never hinted.
+    /// `None` once no tile is unknown.
+    pub fn hint(&self, avoided: &[Coordinates]) -> Option<Hint> {
+        let mut solver = self.clone();
+        loop {
+            let deductions = solver.deduce();
+            if let Some(&coordinates) = deductions
+                .safe
+                .iter()
+                .filter(|coordinates| !avoided.contains(coordinates))
+                .min()
+            {
+                return Some(Hint {
+                    coordinates,
+                    risk: 0.,
+                });
+            }
+            if deductions.mines.is_empty() {
+                return solver.lowest_risk(avoided);
+            }
+            for coordinates in deductions.mines {
+                solver.mark_mine(coordinates);
+            }
+        }
+    }
+
+    /// Unknown tile with the lowest estimated risk, the first one row by row on ties.
+    /// A tile next to revealed counts takes the highest share of mines left among its unknown neighbors,
+    /// any other tile the share of the mines the counts do not account for among the tiles away from them.
+    fn lowest_risk(&self, avoided: &[Coordinates]) -> Option<Hint> {
+        if self.unknown_count() == 0 {
+            return None;
+        }
+        let constraints = self.constraints();
+        let unconstrained_count = self.unconstrained_tiles(&constraints).count();
+        let unconstrained_mines = self
+            .remaining_mines()
+            .saturating_sub(Self::disjoint_mines(&constraints));
+        let density = match unconstrained_count {
+            0 => 1.,
+            count => (unconstrained_mines as f32 / count as f32).min(1.),
+        };
+        let mut risks: HashMap<Coordinates, f32> = HashMap::default();
+        for constraint in constraints.values() {
+            let risk = constraint.mines as f32 / constraint.unknown.len() as f32;
+            for &coordinates in &constraint.unknown {
+                let highest = risks.entry(coordinates).or_insert(0.);
+                *highest = highest.max(risk);
+            }
+        }
+        self.unknown_tiles()
+            .filter(|coordinates| !avoided.contains(coordinates))
+            .map(|coordinates| Hint {
+                coordinates,
+                // Wrong flags can make counts ask for more mines than there are tiles
+                risk: risks.get(&coordinates).map_or(density, |risk| risk.min(1.)),
+            })
+            .min_by(|a, b| a.risk.partial_cmp(&b.risk).unwrap_or(Ordering::Equal))
+    }
+
+    /// Mines not known yet
+    fn remaining_mines(&self) -> usize {
+        (self.mine_count as usize).saturating_sub(self.mines.len())
+    }
+
+    /// Least number of mines the constraints hold together,
+    /// summed over constraints sharing no tile, the ones asking for the most mines first
+    fn disjoint_mines(constraints: &HashMap<Coordinates, Constraint>) -> usize {
+        let mut sorted: Vec<_> = constraints.iter().collect();
+        sorted.sort_unstable_by_key(|(coordinates, constraint)| {
+            (std::cmp::Reverse(constraint.mines), **coordinates)
+        });
+        let mut covered = HashSet::default();
+        let mut mines = 0;
+        for (_, constraint) in sorted {
+            if constraint
+                .unknown
+                .iter()
+                .any(|coordinates| covered.contains(coordinates))
+            {
+                continue;
+            }
+            covered.extend(constraint.unknown.iter().copied());
+            mines += constraint.mines as usize;
+        }
+        mines
+    }
+
+    /// Unknown tiles next to no revealed count, row by row from the bottom left
+    fn unconstrained_tiles<'a>(
+        &'a self,
+        constraints: &'a HashMap<Coordinates, Constraint>,
+    ) -> impl Iterator<Item = Coordinates> + 'a {
+        self.unknown_tiles().filter(move |coordinates| {
+            !constraints
+                .values()
+                .any(|constraint| constraint.unknown.binary_search(coordinates).is_ok())
+        })
+    }
+
+    /// Tiles that are neither revealed nor known mines, row by row from the bottom left
+    fn unknown_tiles(&self) -> impl Iterator<Item = Coordinates> + '_ {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Coordinates { x, y }))
+            .filter(|coordinates| self.is_unknown(*coordinates))
+    }
+
+    /// Builds a constraint for every revealed tile bordering unknown tiles
+    fn constraints(&self) -> HashMap<Coordinates, Constraint> {
+        self.revealed
+            .iter()
+            .filter_map(|(&coordinates, &count)| {
+                let mut unknown = Vec::new();
+                let mut known_mines = 0;
+                for neighbor in self.square(coordinates, 1) {
+                    if self.mines.contains(&neighbor) {
+                        known_mines += 1;
+                    } else if !self.revealed.contains_key(&neighbor) {
+                        unknown.push(neighbor);
+                    }
+                }
+                if unknown.is_empty() {
+                    return None;
+                }
+                unknown.sort_unstable();
+                Some((
+                    coordinates,
+                    Constraint {
+                        unknown,
+                        mines: count.saturating_sub(known_mines),
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// In-bounds coordinates around `coordinates` up to `radius` tiles away, excluding the center
+    fn square(&self, coordinates: Coordinates, radius: u16) -> impl Iterator<Item = Coordinates> {
+        let (width, height) = (self.width, self.height);
+        let min_x = coordinates.x.saturating_sub(radius);
+        let min_y = coordinates.y.saturating_sub(radius);
+        let max_x = coordinates
+            .x
+            .saturating_add(radius)
+            .min(width.saturating_sub(1));
+        let max_y = coordinates
+            .y
+            .saturating_add(radius)
+            .min(height.saturating_sub(1));
+        (min_y..=max_y)
+            .flat_map(move |y| (min_x..=max_x).map(move |x| Coordinates { x, y }))
+            .filter(move |other| *other != coordinates)
+    }
+}
+
+/// Checks whether a tile map can be fully uncovered from `start` without ever having to guess
+pub fn is_solvable_from(tile_map: &TileMap, start: Coordinates) -> bool {
+    let mut solver = Solver::new(tile_map.width, tile_map.height, tile_map.mine_count);
+    let mut to_uncover = vec![start];
+    loop {
+        while let Some(coordinates) = to_uncover.pop() {
+            if !solver.is_unknown(coordinates) {
+                continue;
+            }
+            if tile_map.is_mine_at(coordinates) {
+                return false;
+            }
+            let count = tile_map.neighbor_count_at(coordinates);
+            solver.reveal(coordinates, count);
+            if count == 0 {
+                // Empty tiles propagate like they do in game
+                to_uncover.extend(solver.square(coordinates, 1));
+            }
+        }
+
+        if solver.unknown_count() == 0 {
+            return true;
+        }
+        let deductions = solver.deduce();
+        if deductions.is_empty() {
+            return false;
+        }
+        for coordinates in deductions.mines {
+            solver.mark_mine(coordinates);
+        }
+        to_uncover.extend(deductions.safe);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tile_map_with_mines(width: u16, height: u16, mines: &[(u16, u16)]) -> TileMap {
+        TileMap::from_mines(
+            width,
+            height,
+            mines.iter().map(|&(x, y)| Coordinates { x, y }),
+        )
+    }
+
+    #[test]
+    fn solves_by_single_point_reasoning() {
+        let tile_map = tile_map_with_mines(4, 1, &[(3, 0)]);
+        assert!(is_solvable_from(&tile_map, Coordinates { x: 0, y: 0 }));
+    }
+
+    #[test]
+    fn detects_coin_flip() {
+        let tile_map = tile_map_with_mines(3, 2, &[(2, 0)]);
+        assert!(!is_solvable_from(&tile_map, Coordinates { x: 0, y: 0 }));
+    }
+
+    #[test]
+    fn uses_total_mine_count() {
+        let tile_map = tile_map_with_mines(3, 2, &[(2, 0), (2, 1)]);
+        assert!(is_solvable_from(&tile_map, Coordinates { x: 0, y: 0 }));
+    }
+
+    #[test]
+    fn solves_one_two_pattern() {
+        // y=1: ? ? ? ?
+        // y=0: 1 2 2 1
+        let mut solver = Solver::new(4, 2, 2);
+        solver.reveal(Coordinates { x: 0, y: 0 }, 1);
+        solver.reveal(Coordinates { x: 1, y: 0 }, 2);
+        solver.reveal(Coordinates { x: 2, y: 0 }, 2);
+        solver.reveal(Coordinates { x: 3, y: 0 }, 1);
+        let deductions = solver.deduce();
+        assert!(deductions.safe.is_empty());
+        assert!(deductions.mines.contains(&Coordinates { x: 1, y: 1 }));
+        assert!(deductions.mines.contains(&Coordinates { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn hints_safe_tile_or_lowest_risk() {
+        // The mines deduced from the 1 2 2 1 pattern prove the top left tile safe
+        let mut solver = Solver::new(4, 2, 2);
+        for (x, count) in [1, 2, 2, 1].into_iter().enumerate() {
+            solver.reveal(Coordinates { x: x as u16, y: 0 }, count);
+        }
+        assert_eq!(
+            solver.hint(&[]),
+            Some(Hint {
+                coordinates: Coordinates { x: 0, y: 1 },
+                risk: 0.
+            })
+        );
+
+        // The only mine is next to the 1, so the tiles away from it are safe
+        let mut solver = Solver::new(3, 3, 1);
+        solver.reveal(Coordinates { x: 0, y: 0 }, 1);
+        assert_eq!(
+            solver.hint(&[]),
+            Some(Hint {
+                coordinates: Coordinates { x: 0, y: 2 },
+                risk: 0.
+            })
+        );
+        // A flagged tile is skipped for the next safe one
+        assert_eq!(
+            solver.hint(&[Coordinates { x: 0, y: 2 }]),
+            Some(Hint {
+                coordinates: Coordinates { x: 1, y: 2 },
+                risk: 0.
+            })
+        );
+
+        // Without a proof, tiles away from the 1 are less likely to be mines than its neighbors
+        let mut solver = Solver::new(3, 3, 2);
+        solver.reveal(Coordinates { x: 0, y: 0 }, 1);
+        assert_eq!(
+            solver.hint(&[]),
+            Some(Hint {
+                coordinates: Coordinates { x: 2, y: 0 },
+                risk: 0.2
+            })
+        );
+    }
+}
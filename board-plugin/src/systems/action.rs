@@ -0,0 +1,40 @@
+use crate::events::{
+    BoardActionEvent, HintEvent, RedoEvent, TileChordEvent, TileMarkEvent, TileTriggerEvent,
+    UndoEvent,
+};
+use crate::resources::{BoardStats, ReplayAction};
+use bevy::prelude::*;
+
+/// Passes the board actions on as their tile, undo, redo and hint events, in the order they were sent.
+/// Clicks are counted here, so tiles uncovered by hints are not.
+#[allow(clippy::too_many_arguments)]
+pub fn dispatch_actions(
+    mut stats: ResMut<BoardStats>,
+    mut board_action_event_reader: EventReader<BoardActionEvent>,
+    mut tile_trigger_event_writer: EventWriter<TileTriggerEvent>,
+    mut tile_mark_event_writer: EventWriter<TileMarkEvent>,
+    mut tile_chord_event_writer: EventWriter<TileChordEvent>,
+    mut undo_event_writer: EventWriter<UndoEvent>,
+    mut redo_event_writer: EventWriter<RedoEvent>,
+    mut hint_event_writer: EventWriter<HintEvent>,
+) {
+    for event in board_action_event_reader.iter() {
+        match event.0 {
+            ReplayAction::Trigger(coordinates) => {
+                stats.left_clicks += 1;
+                tile_trigger_event_writer.send(TileTriggerEvent(coordinates))
+            }
+            ReplayAction::Mark(coordinates) => {
+                stats.right_clicks += 1;
+                tile_mark_event_writer.send(TileMarkEvent(coordinates))
+            }
+            ReplayAction::Chord(coordinates) => {
+                stats.chord_clicks += 1;
+                tile_chord_event_writer.send(TileChordEvent(coordinates))
+            }
+            ReplayAction::Undo => undo_event_writer.send(UndoEvent),
+            ReplayAction::Redo => redo_event_writer.send(RedoEvent),
+            ReplayAction::Hint => hint_event_writer.send(HintEvent),
+        }
+    }
+}
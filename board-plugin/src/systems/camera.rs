@@ -0,0 +1,289 @@
+use crate::components::{BoardCamera, TileCursor};
+use crate::resources::{Board, CameraOptions, InputAction, InputMap};
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::log;
+use bevy::prelude::*;
+
+/// Pixels scrolled by the mouse wheel that count as a line
+const PIXELS_PER_LINE: f32 = 100.;
+
+/// Camera drag in progress with the pan button
+#[derive(Debug, Copy, Clone)]
+pub struct CameraDrag {
+    /// Cursor position when the pan button was pressed
+    start: Vec2,
+    /// Cursor position on the previous frame
+    last: Vec2,
+    /// Whether the cursor moved past the drag threshold
+    dragging: bool,
+}
+
+fn window_size(window: &Window) -> Vec2 {
+    Vec2::new(window.width(), window.height())
+}
+
+/// Keeps the camera centered over the board, given its world rectangle
+fn clamp_to_board(transform: &mut Transform, (min, max): (Vec2, Vec2)) {
+    transform.translation.x = transform.translation.x.clamp(min.x, max.x);
+    transform.translation.y = transform.translation.y.clamp(min.y, max.y);
+}
+
+/// Zooms by `factor`, keeping the world position under the `anchor` window position in place
+fn zoom_at(
+    transform: &mut Transform,
+    projection: &mut OrthographicProjection,
+    options: &CameraOptions,
+    window: &Window,
+    anchor: Vec2,
+    factor: f32,
+) {
+    let scale = (projection.scale * factor).clamp(options.min_scale, options.max_scale);
+    let offset = anchor - window_size(window) / 2.;
+    transform.translation += (offset * (projection.scale - scale)).extend(0.);
+    projection.scale = scale;
+}
+
+/// Pans the camera while a mouse button bound to `InputAction::Pan` is dragged
+#[allow(clippy::too_many_arguments)]
+pub fn drag_camera(
+    windows: Res<Windows>,
+    board: Res<Board>,
+    options: Res<CameraOptions>,
+    input_map: Res<InputMap>,
+    buttons: Res<Input<MouseButton>>,
+    mut drag: Local<Option<CameraDrag>>,
+    mut cameras: Query<(&mut Transform, &OrthographicProjection), With<BoardCamera>>,
+    boards: Query<&GlobalTransform>,
+) {
+    let cursor = match windows.get_primary().and_then(Window::cursor_position) {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    if buttons.any_just_pressed(input_map.mouse_buttons(InputAction::Pan)) {
+        *drag = Some(CameraDrag {
+            start: cursor,
+            last: cursor,
+            dragging: false,
+        });
+    }
+    if !buttons.any_pressed(input_map.mouse_buttons(InputAction::Pan)) {
+        *drag = None;
+    }
+    let drag = match drag.as_mut() {
+        Some(drag) => drag,
+        None => return,
+    };
+
+    if !drag.dragging && cursor.distance(drag.start) > options.drag_threshold {
+        log::debug!("Dragging camera");
+        drag.dragging = true;
+        // Catch up with the distance moved before the threshold was crossed
+        drag.last = drag.start;
+    }
+    if drag.dragging {
+        let board_rect = match boards.get(board.entity) {
+            Ok(board_transform) => board.world_rect(board_transform),
+            Err(_) => return,
+        };
+        for (mut transform, projection) in cameras.iter_mut() {
+            transform.translation -= ((cursor - drag.last) * projection.scale).extend(0.);
+            clamp_to_board(&mut transform, board_rect);
+        }
+    }
+    drag.last = cursor;
+}
+
+/// Scrolls the camera with the tile cursor keys held with the `InputAction::Scroll` keys,
+/// the cursor keys alone move the `TileCursor`
+pub fn scroll_camera(
+    time: Res<Time>,
+    board: Res<Board>,
+    options: Res<CameraOptions>,
+    input_map: Res<InputMap>,
+    keys: Res<Input<KeyCode>>,
+    mut cameras: Query<(&mut Transform, &OrthographicProjection), With<BoardCamera>>,
+    boards: Query<&GlobalTransform>,
+) {
+    let pressed = |action| keys.any_pressed(input_map.keys(action));
+    if !pressed(InputAction::Scroll) {
+        return;
+    }
+    let direction = [
+        (InputAction::Left, -Vec2::X),
+        (InputAction::Right, Vec2::X),
+        (InputAction::Down, -Vec2::Y),
+        (InputAction::Up, Vec2::Y),
+    ]
+    .into_iter()
+    .filter(|&(action, _)| pressed(action))
+    .fold(Vec2::ZERO, |sum, (_, direction)| sum + direction);
+    if direction == Vec2::ZERO {
+        return;
+    }
+    let board_rect = match boards.get(board.entity) {
+        Ok(board_transform) => board.world_rect(board_transform),
+        Err(_) => return,
+    };
+    for (mut transform, projection) in cameras.iter_mut() {
+        let distance = direction * options.scroll_speed * projection.scale * time.delta_seconds();
+        transform.translation += distance.extend(0.);
+        clamp_to_board(&mut transform, board_rect);
+    }
+}
+
+/// Zooms the camera with the mouse wheel around the cursor, by pinching,
+/// or with the inputs bound to `InputAction::ZoomIn` and `ZoomOut` around the window center
+#[allow(clippy::too_many_arguments)]
+pub fn zoom_camera(
+    windows: Res<Windows>,
+    board: Res<Board>,
+    options: Res<CameraOptions>,
+    input_map: Res<InputMap>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    mut wheel_event_reader: EventReader<MouseWheel>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<BoardCamera>>,
+    boards: Query<&GlobalTransform>,
+) {
+    let just_pressed =
+        |action| input_map.just_pressed(action, &keys, &mouse_buttons, &gamepad_buttons);
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let center = window_size(window) / 2.;
+    let step = 1. - options.zoom_step;
+
+    let mut zooms = Vec::new();
+    let lines: f32 = wheel_event_reader
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum();
+    if lines != 0. {
+        zooms.push((window.cursor_position().unwrap_or(center), step.powf(lines)));
+    }
+    if just_pressed(InputAction::ZoomIn) {
+        zooms.push((center, step));
+    }
+    if just_pressed(InputAction::ZoomOut) {
+        zooms.push((center, 1. / step));
+    }
+    let touches: Vec<_> = touches.iter().collect();
+    if let [first, second] = touches[..] {
+        let distance = first.position().distance(second.position());
+        let previous_distance = first
+            .previous_position()
+            .distance(second.previous_position());
+        if distance > 0. && previous_distance > 0. && distance != previous_distance {
+            let anchor = (first.position() + second.position()) / 2.;
+            zooms.push((anchor, previous_distance / distance));
+        }
+    }
+
+    if zooms.is_empty() {
+        return;
+    }
+    let board_rect = match boards.get(board.entity) {
+        Ok(board_transform) => board.world_rect(board_transform),
+        Err(_) => return,
+    };
+    for (mut transform, mut projection) in cameras.iter_mut() {
+        for &(anchor, factor) in zooms.iter() {
+            zoom_at(
+                &mut transform,
+                &mut projection,
+                &options,
+                window,
+                anchor,
+                factor,
+            );
+        }
+        clamp_to_board(&mut transform, board_rect);
+    }
+}
+
+/// Zooms out to fit the whole board in the window on `InputAction::Fit` and for every new board.
+/// Boards already fitting in the window get the camera back to its initial position instead.
+#[allow(clippy::too_many_arguments)]
+pub fn fit_camera(
+    windows: Res<Windows>,
+    board: Res<Board>,
+    options: Res<CameraOptions>,
+    input_map: Res<InputMap>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<BoardCamera>>,
+    boards: Query<&GlobalTransform>,
+) {
+    let fit = input_map.just_pressed(InputAction::Fit, &keys, &mouse_buttons, &gamepad_buttons);
+    if !board.is_added() && !fit {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let (min, max) = match boards.get(board.entity) {
+        Ok(board_transform) => board.world_rect(board_transform),
+        Err(_) => return,
+    };
+    let half_window = window_size(window) / 2.;
+    let fits = min.cmpge(-half_window).all() && max.cmple(half_window).all();
+    let available = (window_size(window) - Vec2::splat(options.fit_margin * 2.)).max(Vec2::ONE);
+    let fit = (max - min) / available;
+    let scale = fit.x.max(fit.y).clamp(options.min_scale, options.max_scale);
+    for (mut transform, mut projection) in cameras.iter_mut() {
+        if fits {
+            projection.scale = 1.;
+            transform.translation = Vec2::ZERO.extend(transform.translation.z);
+        } else {
+            projection.scale = scale;
+            let center = (min + max) / 2.;
+            transform.translation = center.extend(transform.translation.z);
+        }
+    }
+}
+
+/// Scrolls the camera just enough to keep the visible tile cursor moved last in view.
+/// Of cursors moved in the same frame, one other than the cursor followed so far wins.
+pub fn follow_tile_cursor(
+    windows: Res<Windows>,
+    board: Res<Board>,
+    mut followed: Local<Option<Entity>>,
+    cursors: Query<(Entity, &TileCursor, &Visibility), Changed<TileCursor>>,
+    mut cameras: Query<(&mut Transform, &OrthographicProjection), With<BoardCamera>>,
+    boards: Query<&GlobalTransform>,
+) {
+    let (entity, cursor, _) = match cursors
+        .iter()
+        .filter(|(_, _, visibility)| visibility.is_visible)
+        .max_by_key(|(entity, _, _)| Some(*entity) != *followed)
+    {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    *followed = Some(entity);
+    let (window, board_transform) = match (windows.get_primary(), boards.get(board.entity)) {
+        (Some(window), Ok(board_transform)) => (window, board_transform),
+        _ => return,
+    };
+    let tile_center = (Vec2::new(cursor.coordinates.x as f32, cursor.coordinates.y as f32)
+        + Vec2::splat(0.5))
+        * board.tile_size;
+    let position = board_transform.mul_vec3(tile_center.extend(0.)).truncate();
+    for (mut transform, projection) in cameras.iter_mut() {
+        // Keeps a tile of margin between the cursor and the window edges
+        let half_view = (window_size(window) / 2. * projection.scale
+            - Vec2::splat(board.tile_size))
+        .max(Vec2::ZERO);
+        let center = transform.translation.truncate();
+        let center = center.clamp(position - half_view, position + half_view);
+        transform.translation = center.extend(transform.translation.z);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::assets::board::BoardAssets;
+use crate::assets::tile_atlas::{TileAtlas, TileFace};
+use crate::components::{Coordinates, TileChunk};
+use crate::events::{
+    ExplosionUndoneEvent, MineExplodedEvent, TileCoveredEvent, TileFlaggedEvent, TileUncoveredEvent,
+};
+use crate::resources::{Board, BoardStatus, Tile};
+use bevy::log;
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy::render::render_resource::PrimitiveTopology;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+use bevy::utils::HashSet;
+
+/// Look of a tile, following the sprites of boards rendered with entities
+fn tile_face(board: &Board, marked: &HashSet<Coordinates>, coordinates: Coordinates) -> TileFace {
+    let tile = board
+        .tile_map
+        .get(coordinates)
+        .copied()
+        .unwrap_or(Tile::Empty);
+    let lost = board.status == BoardStatus::Lost;
+    if board.is_covered(coordinates) {
+        match (marked.contains(&coordinates), tile == Tile::Mine) {
+            (true, false) if lost => TileFace::WrongFlag,
+            (true, _) => TileFace::Flagged,
+            (false, true) if lost => TileFace::Mine,
+            (false, _) if board.is_questioned(coordinates) => TileFace::Questioned,
+            (false, _) => TileFace::Covered,
+        }
+    } else {
+        match tile {
+            Tile::Empty => TileFace::Empty,
+            Tile::Neighbor(count) => TileFace::Neighbor(count),
+            // Only the first uncovered mine explodes, chording wrong flags may uncover more
+            Tile::Mine if board.exploded == Some(coordinates) => TileFace::ExplodedMine,
+            Tile::Mine => TileFace::Mine,
+        }
+    }
+}
+
+/// Texture coordinates of every vertex of a chunk
+fn chunk_uvs(board: &Board, marked: &HashSet<Coordinates>, chunk: &TileChunk) -> Vec<[f32; 2]> {
+    chunk
+        .coordinates()
+        .flat_map(|coordinates| {
+            let (min, max) = tile_face(board, marked, coordinates).uv_rect();
+            // Bottom left, bottom right, top right, top left
+            [
+                [min.x, max.y],
+                [max.x, max.y],
+                [max.x, min.y],
+                [min.x, min.y],
+            ]
+        })
+        .collect()
+}
+
+/// One quad per tile, relative to the chunk origin
+pub(crate) fn chunk_mesh(board: &Board, marked: &HashSet<Coordinates>, chunk: &TileChunk) -> Mesh {
+    let size = board.tile_size;
+    let half = board.tile_sprite_size() / 2.;
+    let tile_count = chunk.width as usize * chunk.height as usize;
+    let mut positions = Vec::with_capacity(tile_count * 4);
+    let mut indices = Vec::with_capacity(tile_count * 6);
+    for coordinates in chunk.coordinates() {
+        let x = (coordinates.x - chunk.origin.x) as f32 * size + size / 2.;
+        let y = (coordinates.y - chunk.origin.y) as f32 * size + size / 2.;
+        let first = positions.len() as u32;
+        positions.extend([
+            [x - half, y - half, 0.],
+            [x + half, y - half, 0.],
+            [x + half, y + half, 0.],
+            [x - half, y + half, 0.],
+        ]);
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0., 0., 1.]; positions.len()]);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, chunk_uvs(board, marked, chunk));
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// Position of a chunk relative to its board
+pub(crate) fn chunk_translation(board: &Board, chunk: &TileChunk) -> Vec3 {
+    Vec3::new(
+        chunk.origin.x as f32 * board.tile_size,
+        chunk.origin.y as f32 * board.tile_size,
+        // Drawn over the board background
+        1.,
+    )
+}
+
+/// Creates the meshes of new chunks, along with the tile atlas of their board
+#[allow(clippy::too_many_arguments)]
+pub fn build_tile_chunks(
+    mut commands: Commands,
+    board: Res<Board>,
+    board_assets: Res<BoardAssets>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    chunks: Query<(Entity, &TileChunk), Added<TileChunk>>,
+) {
+    if chunks.is_empty() {
+        return;
+    }
+    // Drawn again for every board, in case the board assets changed
+    let image = TileAtlas::draw(&board_assets, &images);
+    let image = images.add(image);
+    let material = materials.add(ColorMaterial::from(image.clone()));
+    let marked = board.marked_tiles.iter().copied().collect();
+    for (entity, chunk) in chunks.iter() {
+        commands.entity(entity).insert_bundle(MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(chunk_mesh(&board, &marked, chunk))),
+            material: material.clone(),
+            transform: Transform::from_translation(chunk_translation(&board, chunk)),
+            ..Default::default()
+        });
+    }
+    commands.insert_resource(TileAtlas { image, material });
+}
+
+/// Updates the chunks of the tiles changed this frame
+#[allow(clippy::too_many_arguments)]
+pub fn update_tile_chunks(
+    board: Res<Board>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut tile_uncovered_event_reader: EventReader<TileUncoveredEvent>,
+    mut tile_flagged_event_reader: EventReader<TileFlaggedEvent>,
+    mut tile_covered_event_reader: EventReader<TileCoveredEvent>,
+    mut mine_exploded_event_reader: EventReader<MineExplodedEvent>,
+    mut explosion_undone_event_reader: EventReader<ExplosionUndoneEvent>,
+    chunks: Query<(&TileChunk, &Mesh2dHandle)>,
+) {
+    let mut changed: Vec<_> = tile_uncovered_event_reader
+        .iter()
+        .map(|event| event.coordinates)
+        .chain(
+            tile_flagged_event_reader
+                .iter()
+                .map(|event| event.coordinates),
+        )
+        .chain(
+            tile_covered_event_reader
+                .iter()
+                .map(|event| event.coordinates),
+        )
+        .collect();
+    // The whole board is revealed once a mine exploded, and concealed once the explosion is undone
+    let revealed = mine_exploded_event_reader.iter().count() > 0
+        || explosion_undone_event_reader.iter().count() > 0;
+    if changed.is_empty() && !revealed {
+        return;
+    }
+    changed.sort_unstable();
+    changed.dedup();
+
+    let marked = board.marked_tiles.iter().copied().collect();
+    for (chunk, mesh) in chunks.iter() {
+        if !revealed
+            && !changed
+                .iter()
+                .any(|coordinates| chunk.contains(*coordinates))
+        {
+            continue;
+        }
+        match meshes.get_mut(&mesh.0) {
+            Some(mesh) => {
+                mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, chunk_uvs(&board, &marked, chunk))
+            }
+            None => log::error!("Missing mesh of the chunk at {}", chunk.origin),
+        }
+    }
+}
+
+/// Draws the tile atlas again when one of its textures is loaded or modified
+pub fn update_tile_atlas(
+    atlas: Option<Res<TileAtlas>>,
+    board_assets: Res<BoardAssets>,
+    mut images: ResMut<Assets<Image>>,
+    mut image_event_reader: EventReader<AssetEvent<Image>>,
+) {
+    let sources = TileAtlas::sources(&board_assets);
+    let changed = image_event_reader.iter().any(|event| match event {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+            sources.contains(&handle)
+        }
+        AssetEvent::Removed { .. } => false,
+    });
+    let atlas = match atlas {
+        Some(atlas) if changed => atlas,
+        _ => return,
+    };
+    let image = TileAtlas::draw(&board_assets, &images);
+    if let Some(atlas_image) = images.get_mut(&atlas.image) {
+        *atlas_image = image;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::resources::{test_board, TileMap};
+
+    #[test]
+    fn faces_follow_board_state() {
+        let mine = Coordinates { x: 0, y: 0 };
+        let flagged = Coordinates { x: 1, y: 0 };
+        let uncovered = Coordinates { x: 2, y: 0 };
+        let questioned = Coordinates { x: 3, y: 0 };
+        let exploded = Coordinates { x: 4, y: 0 };
+        let uncovered_mine = Coordinates { x: 5, y: 0 };
+        let mut board = Board {
+            marked_tiles: vec![flagged],
+            questioned_tiles: vec![questioned],
+            question_marks: true,
+            chunk_size: Some(4),
+            ..test_board(TileMap::from_mines(6, 1, [mine, exploded, uncovered_mine]))
+        };
+        for coordinates in [mine, flagged, questioned] {
+            board.cover_tile(coordinates);
+        }
+        let marked = board.marked_tiles.iter().copied().collect();
+
+        let faces = |board: &Board| {
+            [mine, flagged, uncovered, questioned]
+                .map(|coordinates| tile_face(board, &marked, coordinates))
+        };
+        assert_eq!(
+            faces(&board),
+            [
+                TileFace::Covered,
+                TileFace::Flagged,
+                TileFace::Empty,
+                TileFace::Questioned
+            ]
+        );
+        board.status = BoardStatus::Lost;
+        board.exploded = Some(exploded);
+        assert_eq!(
+            [exploded, uncovered_mine].map(|coordinates| tile_face(&board, &marked, coordinates)),
+            [TileFace::ExplodedMine, TileFace::Mine]
+        );
+        assert_eq!(
+            faces(&board),
+            [
+                TileFace::Mine,
+                TileFace::WrongFlag,
+                TileFace::Empty,
+                TileFace::Questioned
+            ]
+        );
+    }
+}
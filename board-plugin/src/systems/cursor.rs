@@ -0,0 +1,146 @@
+use crate::components::{Coordinates, TileCursor};
+use crate::events::BoardActionEvent;
+use crate::resources::{Board, CursorOptions, InputAction, InputMap, ReplayAction, ReplayPlayer};
+use bevy::input::mouse::MouseButtonInput;
+use bevy::log;
+use bevy::prelude::*;
+
+/// Z of the tile cursor relative to the board, over tiles, covers and flags
+const CURSOR_Z: f32 = 5.;
+
+/// Tile actions pressed for a tile cursor in a frame
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct CursorActions {
+    uncover: bool,
+    mark: bool,
+    chord: bool,
+}
+
+impl CursorActions {
+    /// Reads the actions whose bound inputs were just pressed
+    pub(crate) fn pressed(pressed: impl Fn(InputAction) -> bool) -> Self {
+        Self {
+            uncover: pressed(InputAction::Uncover),
+            mark: pressed(InputAction::Mark),
+            chord: pressed(InputAction::Chord),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.uncover && !self.mark && !self.chord
+    }
+}
+
+/// Sends the board actions of a tile cursor on the tile at `coordinates`, like the mouse buttons would.
+/// Uncovering an uncovered tile chords it, like clicking it.
+pub(crate) fn act_on_cursor_tile(
+    board: &Board,
+    coordinates: Coordinates,
+    actions: CursorActions,
+    board_action_event_writer: &mut EventWriter<BoardActionEvent>,
+) {
+    if actions.chord || (actions.uncover && !board.is_covered(coordinates)) {
+        log::info!("Trying to chord a tile at {}", coordinates);
+        board_action_event_writer.send(BoardActionEvent(ReplayAction::Chord(coordinates)));
+    } else if actions.uncover {
+        log::info!("Trying to uncover a tile at {}", coordinates);
+        board_action_event_writer.send(BoardActionEvent(ReplayAction::Trigger(coordinates)));
+    }
+    if actions.mark {
+        log::info!("Trying to mark a tile at {}", coordinates);
+        board_action_event_writer.send(BoardActionEvent(ReplayAction::Mark(coordinates)));
+    }
+}
+
+/// Moves the keyboard tile cursor and acts on its tile, sending the same events as mouse clicks.
+/// The first key press only shows the cursor. Keys held with the `InputAction::Scroll` keys are left to the camera.
+pub fn keyboard_input(
+    board: Res<Board>,
+    options: Res<CursorOptions>,
+    input_map: Res<InputMap>,
+    keys: Res<Input<KeyCode>>,
+    replay_player: Option<Res<ReplayPlayer>>,
+    mut cursors: Query<(&mut TileCursor, &mut Visibility)>,
+    mut board_action_event_writer: EventWriter<BoardActionEvent>,
+) {
+    if !board.is_playing() || replay_player.is_some() {
+        return;
+    }
+    if keys.any_pressed(input_map.keys(InputAction::Scroll)) {
+        return;
+    }
+    let pressed = |action| keys.any_just_pressed(input_map.keys(action));
+    let direction = [
+        (InputAction::Up, (0, 1)),
+        (InputAction::Down, (0, -1)),
+        (InputAction::Left, (-1, 0)),
+        (InputAction::Right, (1, 0)),
+    ]
+    .into_iter()
+    .filter(|&(action, _)| pressed(action))
+    .fold((0, 0), |(x, y), (_, (dx, dy))| (x + dx, y + dy));
+    let actions = CursorActions::pressed(pressed);
+    if direction == (0, 0) && actions.is_empty() {
+        return;
+    }
+
+    for (mut cursor, mut visibility) in cursors.iter_mut() {
+        if cursor.gamepad.is_some() {
+            continue;
+        }
+        if !visibility.is_visible {
+            visibility.is_visible = true;
+            continue;
+        }
+        if direction != (0, 0) {
+            let size = (board.tile_map.width, board.tile_map.height);
+            cursor.coordinates = cursor.step(direction, size, options.wrap);
+        }
+
+        act_on_cursor_tile(
+            &board,
+            cursor.coordinates,
+            actions,
+            &mut board_action_event_writer,
+        );
+    }
+}
+
+/// Moves the tile cursor sprite over its tile
+pub fn place_tile_cursor(
+    board: Res<Board>,
+    mut cursors: Query<(
+        ChangeTrackers<TileCursor>,
+        &TileCursor,
+        &mut Transform,
+        &mut Sprite,
+    )>,
+) {
+    for (tracker, cursor, mut transform, mut sprite) in cursors.iter_mut() {
+        if !tracker.is_changed() && !board.is_changed() {
+            continue;
+        }
+        let size = board.tile_size;
+        transform.translation = Vec3::new(
+            (cursor.coordinates.x as f32 + 0.5) * size,
+            (cursor.coordinates.y as f32 + 0.5) * size,
+            CURSOR_Z,
+        );
+        sprite.custom_size = Some(Vec2::splat(size));
+    }
+}
+
+/// Hides the keyboard tile cursor once the mouse is used
+pub fn hide_tile_cursor(
+    mut button_event_reader: EventReader<MouseButtonInput>,
+    mut cursors: Query<(&TileCursor, &mut Visibility)>,
+) {
+    if button_event_reader.iter().count() == 0 {
+        return;
+    }
+    for (cursor, mut visibility) in cursors.iter_mut() {
+        if cursor.gamepad.is_none() {
+            visibility.is_visible = false;
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::assets::board::BoardAssets;
+use crate::components::TileCursor;
+use crate::events::BoardActionEvent;
+use crate::resources::{Board, CursorOptions, GamepadOptions, InputAction, InputMap, ReplayPlayer};
+use crate::systems::cursor::{act_on_cursor_tile, CursorActions};
+use crate::systems::startup::spawn_tile_cursor;
+use bevy::log;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// Direction held on a gamepad, repeating cursor moves while held
+#[derive(Debug, Default, Copy, Clone)]
+pub struct CursorRepeat {
+    direction: (i8, i8),
+    /// Seconds until the next repeated move
+    timer: f32,
+}
+
+impl CursorRepeat {
+    /// Holds `direction` for another `delta` seconds, returning whether the cursor moves
+    fn update(&mut self, direction: (i8, i8), delta: f32, options: &GamepadOptions) -> bool {
+        if direction != self.direction {
+            self.direction = direction;
+            self.timer = options.repeat_delay;
+            return direction != (0, 0);
+        }
+        if direction == (0, 0) {
+            return false;
+        }
+        self.timer -= delta;
+        if self.timer > 0. {
+            return false;
+        }
+        self.timer += options.repeat_interval;
+        true
+    }
+}
+
+/// Gives every connected gamepad its own tile cursor, removing the cursors of disconnected ones
+pub fn sync_gamepad_cursors(
+    mut commands: Commands,
+    board: Res<Board>,
+    board_assets: Res<BoardAssets>,
+    gamepads: Res<Gamepads>,
+    cursors: Query<(Entity, &TileCursor)>,
+) {
+    for (entity, cursor) in cursors.iter() {
+        if let Some(gamepad) = cursor.gamepad {
+            if !gamepads.contains(&gamepad) {
+                log::info!("Removing tile cursor of disconnected {:?}", gamepad);
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+    for &gamepad in gamepads.iter() {
+        if cursors
+            .iter()
+            .any(|(_, cursor)| cursor.gamepad == Some(gamepad))
+        {
+            continue;
+        }
+        log::info!("Adding tile cursor for {:?}", gamepad);
+        let size = (board.tile_map.width, board.tile_map.height);
+        commands.entity(board.entity).with_children(|parent| {
+            spawn_tile_cursor(parent, &board_assets, size, Some(gamepad));
+        });
+    }
+}
+
+/// Moves the tile cursor of each gamepad and acts on its tile, sending the same events as mouse clicks.
+/// The first input only shows the cursor.
+#[allow(clippy::too_many_arguments)]
+pub fn gamepad_input(
+    time: Res<Time>,
+    board: Res<Board>,
+    cursor_options: Res<CursorOptions>,
+    options: Res<GamepadOptions>,
+    input_map: Res<InputMap>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    replay_player: Option<Res<ReplayPlayer>>,
+    mut repeats: Local<HashMap<Gamepad, CursorRepeat>>,
+    mut cursors: Query<(&mut TileCursor, &mut Visibility)>,
+    mut board_action_event_writer: EventWriter<BoardActionEvent>,
+) {
+    if !board.is_playing() || replay_player.is_some() {
+        return;
+    }
+
+    for (mut cursor, mut visibility) in cursors.iter_mut() {
+        let gamepad = match cursor.gamepad {
+            Some(gamepad) => gamepad,
+            None => continue,
+        };
+        let pressed = |action| {
+            input_map
+                .gamepad_buttons(action)
+                .any(|button| buttons.just_pressed(GamepadButton(gamepad, button)))
+        };
+        let held = |action| {
+            input_map
+                .gamepad_buttons(action)
+                .any(|button| buttons.pressed(GamepadButton(gamepad, button))) as i8
+        };
+        let axis = |axis| {
+            let value = axes.get(GamepadAxis(gamepad, axis)).unwrap_or(0.);
+            if value >= options.stick_threshold {
+                1
+            } else if value <= -options.stick_threshold {
+                -1
+            } else {
+                0
+            }
+        };
+        let dpad = (
+            held(InputAction::Right) - held(InputAction::Left),
+            held(InputAction::Up) - held(InputAction::Down),
+        );
+        let direction = match dpad {
+            (0, 0) => (
+                axis(GamepadAxisType::LeftStickX),
+                axis(GamepadAxisType::LeftStickY),
+            ),
+            dpad => dpad,
+        };
+        let moves =
+            repeats
+                .entry(gamepad)
+                .or_default()
+                .update(direction, time.delta_seconds(), &options);
+        let actions = CursorActions::pressed(pressed);
+        if !moves && actions.is_empty() {
+            continue;
+        }
+        if !visibility.is_visible {
+            visibility.is_visible = true;
+            continue;
+        }
+        if moves {
+            let size = (board.tile_map.width, board.tile_map.height);
+            cursor.coordinates = cursor.step(direction, size, cursor_options.wrap);
+        }
+
+        act_on_cursor_tile(
+            &board,
+            cursor.coordinates,
+            actions,
+            &mut board_action_event_writer,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn repeats_held_direction() {
+        let options = GamepadOptions {
+            repeat_delay: 0.5,
+            repeat_interval: 0.25,
+            ..Default::default()
+        };
+        let mut repeat = CursorRepeat::default();
+        let moves: Vec<_> = (0..8)
+            .map(|_| repeat.update((1, 0), 0.125, &options))
+            .collect();
+        // Moves on press, then every interval once the delay is over
+        assert_eq!(
+            moves,
+            vec![true, false, false, false, true, false, true, false]
+        );
+        assert!(!repeat.update((0, 0), 0.125, &options));
+        assert!(repeat.update((0, -1), 0.125, &options));
+    }
+}
@@ -0,0 +1,88 @@
+use crate::components::{Coordinates, TileCursor};
+use crate::events::{BoardActionEvent, HintEvent, TileHintedEvent, TileTriggerEvent};
+use crate::resources::{
+    Board, BoardStats, InputAction, InputMap, ReplayAction, ReplayPlayer, Tile,
+};
+use crate::solver::{Hint, Solver};
+use bevy::log;
+use bevy::prelude::*;
+
+/// Asks for hints with the inputs bound in the `InputMap`, recorded as `ReplayAction::Hint`
+pub fn hint_input(
+    input_map: Res<InputMap>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    replay_player: Option<Res<ReplayPlayer>>,
+    mut board_action_event_writer: EventWriter<BoardActionEvent>,
+) {
+    if replay_player.is_some() {
+        return;
+    }
+    if input_map.just_pressed(InputAction::Hint, &keys, &mouse_buttons, &gamepad_buttons) {
+        board_action_event_writer.send(BoardActionEvent(ReplayAction::Hint));
+    }
+}
+
+/// Asks the solver for a tile to play on hint events, moves the keyboard tile cursor onto it
+/// and uncovers it if it is provably safe from the uncovered counts alone.
+/// Flagged tiles are never hinted, and hints are ignored while tiles are being uncovered.
+/// Replays record the hint itself, the tile it uncovers is neither recorded nor counted as a click.
+pub fn give_hints(
+    board: Res<Board>,
+    mut stats: ResMut<BoardStats>,
+    mut cursors: Query<(&mut TileCursor, &mut Visibility)>,
+    mut hint_event_reader: EventReader<HintEvent>,
+    mut tile_hinted_event_writer: EventWriter<TileHintedEvent>,
+    mut tile_trigger_event_writer: EventWriter<TileTriggerEvent>,
+) {
+    if hint_event_reader.iter().count() == 0 || !board.is_playing() || board.is_uncovering() {
+        return;
+    }
+    let hint = match board.deferred_placement {
+        // Mines are placed away from the first uncovered tile
+        Some(_) => Some(Hint {
+            coordinates: Coordinates {
+                x: board.tile_map.width / 2,
+                y: board.tile_map.height / 2,
+            },
+            risk: 0.,
+        })
+        .filter(|hint| !board.marked_tiles.contains(&hint.coordinates)),
+        None => visible_solver(&board).hint(&board.marked_tiles),
+    };
+    let Hint { coordinates, risk } = match hint {
+        Some(hint) => hint,
+        None => return,
+    };
+    log::info!("Hinting tile at {} ({:.0}% risk)", coordinates, risk * 100.);
+    stats.hints += 1;
+    for (mut cursor, mut visibility) in cursors.iter_mut() {
+        if cursor.gamepad.is_none() {
+            cursor.coordinates = coordinates;
+            visibility.is_visible = true;
+        }
+    }
+    if risk == 0. {
+        tile_trigger_event_writer.send(TileTriggerEvent(coordinates));
+    }
+    tile_hinted_event_writer.send(TileHintedEvent { coordinates, risk });
+}
+
+/// Solver knowing the uncovered tiles only. Flags are left out,
+/// so a wrong flag never gets a mine uncovered as a provably safe hint.
+fn visible_solver(board: &Board) -> Solver {
+    let tile_map = &board.tile_map;
+    let mut solver = Solver::new(tile_map.width, tile_map.height, board.mine_count());
+    for (coordinates, tile) in tile_map.iter_with_coordinates() {
+        if board.is_covered(coordinates) {
+            continue;
+        }
+        match *tile {
+            Tile::Empty => solver.reveal(coordinates, 0),
+            Tile::Neighbor(count) => solver.reveal(coordinates, count),
+            Tile::Mine => solver.mark_mine(coordinates),
+        }
+    }
+    solver
+}
@@ -0,0 +1,172 @@
+use crate::assets::board::BoardAssets;
+use crate::components::Coordinates;
+use crate::events::{
+    BoardActionEvent, ExplosionUndoneEvent, MineExplodedEvent, RedoEvent, TileCoveredEvent,
+    TileFlaggedEvent, TileUncoveredEvent, UndoEvent,
+};
+use crate::resources::{
+    Board, BoardHistory, BoardMove, BoardStats, BoardStatus, InputAction, InputMap, ReplayAction,
+    ReplayPlayer, TileMark,
+};
+use crate::systems::mark::{despawn_mark, spawn_mark};
+use crate::systems::startup::spawn_cover;
+use bevy::log;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// Sends undo and redo events for the inputs bound in the `InputMap`
+pub fn history_input(
+    input_map: Res<InputMap>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    replay_player: Option<Res<ReplayPlayer>>,
+    mut board_action_event_writer: EventWriter<BoardActionEvent>,
+) {
+    if replay_player.is_some() {
+        return;
+    }
+    let just_pressed =
+        |action| input_map.just_pressed(action, &keys, &mouse_buttons, &gamepad_buttons);
+    if just_pressed(InputAction::Undo) {
+        board_action_event_writer.send(BoardActionEvent(ReplayAction::Undo));
+    }
+    if just_pressed(InputAction::Redo) {
+        board_action_event_writer.send(BoardActionEvent(ReplayAction::Redo));
+    }
+}
+
+/// Undoes and redoes the moves of the `BoardHistory`, once tiles are done uncovering.
+/// Won games can not be undone.
+#[allow(clippy::too_many_arguments)]
+pub fn undo_moves(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    mut stats: ResMut<BoardStats>,
+    history: Option<ResMut<BoardHistory>>,
+    board_assets: Res<BoardAssets>,
+    // Requests waiting for the tiles being uncovered, `true` for undo
+    mut requests: Local<Vec<bool>>,
+    mut undo_event_reader: EventReader<UndoEvent>,
+    mut redo_event_reader: EventReader<RedoEvent>,
+    tiles: Query<(Entity, &Coordinates)>,
+    covers: Query<&Children>,
+    mut tile_covered_event_writer: EventWriter<TileCoveredEvent>,
+    mut tile_uncovered_event_writer: EventWriter<TileUncoveredEvent>,
+    mut tile_flagged_event_writer: EventWriter<TileFlaggedEvent>,
+    mut mine_exploded_event_writer: EventWriter<MineExplodedEvent>,
+    mut explosion_undone_event_writer: EventWriter<ExplosionUndoneEvent>,
+) {
+    requests.extend(undo_event_reader.iter().map(|_| true));
+    requests.extend(redo_event_reader.iter().map(|_| false));
+    let mut history = match history {
+        Some(history) => history,
+        None => {
+            requests.clear();
+            return;
+        }
+    };
+    // Propagation still adds tiles to the last move
+    if requests.is_empty() || board.is_uncovering() {
+        return;
+    }
+    if board.status == BoardStatus::Won {
+        requests.clear();
+        return;
+    }
+    let sprite_size = board.tile_sprite_size();
+    // Empty on chunked boards, which have no tile entities
+    let tile_entities: HashMap<_, _> = tiles
+        .iter()
+        .map(|(entity, &coordinates)| (coordinates, entity))
+        .collect();
+    // Replaces the mark sprite on the cover of a tile and tells the change
+    let mut remark = |commands: &mut Commands,
+                      board: &mut Board,
+                      coordinates: Coordinates,
+                      mark: Option<TileMark>| {
+        if !board.set_mark(coordinates, mark) {
+            return;
+        }
+        despawn_mark(commands, coordinates, board, &covers);
+        if let Some(mark) = mark {
+            spawn_mark(commands, coordinates, mark, &board_assets, board);
+        }
+        tile_flagged_event_writer.send(TileFlaggedEvent {
+            coordinates,
+            flagged: mark == Some(TileMark::Flag),
+            questioned: mark == Some(TileMark::Question),
+        });
+    };
+
+    for undo in requests.drain(..) {
+        if undo {
+            let BoardMove {
+                uncovered,
+                marked,
+                exploded,
+            } = match history.undo() {
+                Some(undone) => undone,
+                None => continue,
+            };
+            log::info!("Undoing move");
+            if let Some((coordinates, previous, _)) = marked {
+                remark(&mut commands, &mut board, coordinates, previous);
+            }
+            for (coordinates, mark) in uncovered {
+                board.cover_tile(coordinates);
+                if let Some(&tile) = tile_entities.get(&coordinates) {
+                    commands.entity(tile).with_children(|parent| {
+                        let cover = spawn_cover(parent, &board_assets, sprite_size);
+                        board.covers.insert(coordinates, cover);
+                    });
+                }
+                if mark.is_some() {
+                    remark(&mut commands, &mut board, coordinates, mark);
+                }
+                tile_covered_event_writer.send(TileCoveredEvent { coordinates });
+            }
+            if let Some(coordinates) = exploded {
+                board.status = BoardStatus::Playing;
+                board.exploded = None;
+                explosion_undone_event_writer.send(ExplosionUndoneEvent { coordinates });
+            }
+            if history.assisted {
+                stats.assisted = true;
+            }
+        } else {
+            let BoardMove {
+                uncovered,
+                marked,
+                exploded,
+            } = match history.redo() {
+                Some(redone) => redone,
+                None => continue,
+            };
+            log::info!("Redoing move");
+            if let Some((coordinates, _, mark)) = marked {
+                remark(&mut commands, &mut board, coordinates, mark);
+            }
+            for (coordinates, _) in uncovered {
+                if board.uncover_tile(coordinates) {
+                    if let Some(cover) = board.covers.remove(&coordinates) {
+                        commands.entity(cover).despawn_recursive();
+                    }
+                }
+                if let Some(&tile) = board.tile_map.get(coordinates) {
+                    tile_uncovered_event_writer.send(TileUncoveredEvent { coordinates, tile });
+                }
+            }
+            if let Some(coordinates) = exploded {
+                board.status = BoardStatus::Lost;
+                board.exploded = Some(coordinates);
+                mine_exploded_event_writer.send(MineExplodedEvent {
+                    coordinates,
+                    elapsed: stats.elapsed,
+                    clicks: stats.clicks(),
+                    seed: board.seed,
+                });
+            }
+        }
+    }
+}
@@ -1,39 +1,127 @@
-use crate::events::{TileMarkEvent, TileTriggerEvent};
-use crate::resources::Board;
+use super::ui::is_over_ui;
+use crate::components::BoardCamera;
+use crate::events::BoardActionEvent;
+use crate::resources::{
+    Board, CameraOptions, InputAction, InputBinding, InputMap, ReplayAction, ReplayPlayer,
+};
 use bevy::input::{mouse::MouseButtonInput, ElementState};
 use bevy::log;
 use bevy::prelude::*;
+use bevy::ui::CalculatedClip;
 
+/// Sends tile events for mouse clicks on the board, bound in the `InputMap`, picked through the `BoardCamera` wherever the board is placed.
+/// Clicks of the buttons bound to `InputAction::Pan` act on release, unless the cursor was dragged in between.
+/// Mark clicks act on release too, so pressing the mark button first when chording with both buttons does not mark the tile.
+/// Presses over UI nodes with an `Interaction`, such as buttons, are left to the UI.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn input_handling(
     windows: Res<Windows>,
     board: Res<Board>,
+    buttons: Res<Input<MouseButton>>,
+    camera_options: Res<CameraOptions>,
+    input_map: Res<InputMap>,
+    replay_player: Option<Res<ReplayPlayer>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<BoardCamera>>,
+    transforms: Query<&GlobalTransform>,
+    ui_nodes: Query<(&Node, &GlobalTransform, Option<&CalculatedClip>), With<Interaction>>,
+    // Button pressed and where, waiting to act on release
+    mut pending_press: Local<Option<(MouseButton, Vec2)>>,
     mut button_event_reader: EventReader<MouseButtonInput>,
-    mut tile_trigger_event_writer: EventWriter<TileTriggerEvent>,
-    mut tile_mark_event_writer: EventWriter<TileMarkEvent>,
+    mut board_action_event_writer: EventWriter<BoardActionEvent>,
 ) {
+    if !board.is_playing() || replay_player.is_some() {
+        // Drop clicks instead of queuing them for the next game or mixing them with a replay
+        button_event_reader.iter().for_each(drop);
+        *pending_press = None;
+        return;
+    }
     let window = windows
         .get_primary()
         .expect("Error: No primary window found when trying to handle input");
-    for (button, coordinates) in button_event_reader
-        .iter()
-        .filter(|event| event.state == ElementState::Pressed)
-        .filter_map(|event| {
-            let position = window.cursor_position()?;
-            log::trace!("Mouse button pressed: {:?} at {}", event.button, position);
-            let coordinates = board.convert_mouse_to_coordinates(position, window)?;
-            Some((event.button, coordinates))
-        })
-    {
-        match button {
-            MouseButton::Left => {
-                log::info!("Trying to uncover a tile at {}", coordinates);
-                tile_trigger_event_writer.send(TileTriggerEvent(coordinates));
+    let (camera, camera_transform) = match cameras.iter().next() {
+        Some(camera) => camera,
+        None => {
+            log::warn!("No `BoardCamera` found to pick tiles through");
+            button_event_reader.iter().for_each(drop);
+            return;
+        }
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+    let board_transform = match transforms.get(board.entity) {
+        Ok(transform) => transform,
+        Err(error) => {
+            log::error!("{}", error);
+            button_event_reader.iter().for_each(drop);
+            return;
+        }
+    };
+    // Chording is done with its own button, the uncover and mark buttons together, or by uncovering an uncovered tile
+    let pressed = |action| buttons.any_pressed(input_map.mouse_buttons(action));
+    let both_pressed = pressed(InputAction::Uncover) && pressed(InputAction::Mark);
+
+    let mut chorded = false;
+    for event in button_event_reader.iter() {
+        let position = match window.cursor_position() {
+            Some(position) => position,
+            None => continue,
+        };
+        log::trace!(
+            "Mouse button {:?}: {:?} at {}",
+            event.state,
+            event.button,
+            position
+        );
+        if event.state == ElementState::Pressed && is_over_ui(position, ui_nodes.iter()) {
+            log::debug!("Ignoring {:?} click over the UI", event.button);
+            continue;
+        }
+        let binding = InputBinding::Mouse(event.button);
+        let is_pan_button = input_map.is_bound(InputAction::Pan, binding);
+        let deferred = is_pan_button || input_map.is_bound(InputAction::Mark, binding);
+        match (event.state, deferred) {
+            // Both buttons pressed in the same frame chord once
+            (ElementState::Pressed, _) if both_pressed && chorded => continue,
+            (ElementState::Pressed, _) if both_pressed => {
+                *pending_press = None;
+                chorded = true;
             }
-            MouseButton::Right => {
-                log::info!("Trying to mark a tile at {}", coordinates);
-                tile_mark_event_writer.send(TileMarkEvent(coordinates));
+            (ElementState::Pressed, true) => {
+                *pending_press = Some((event.button, position));
+                continue;
             }
-            _ => {}
+            (ElementState::Pressed, false) => (),
+            (ElementState::Released, true) => match pending_press.take() {
+                Some((button, start))
+                    if button == event.button
+                        && (!is_pan_button
+                            || start.distance(position) <= camera_options.drag_threshold) => {}
+                _ => continue,
+            },
+            (ElementState::Released, false) => continue,
+        }
+        let ray = BoardCamera::screen_to_ray(position, window_size, camera, camera_transform);
+        let coordinates = match board.pick_tile(board_transform, ray) {
+            Some(coordinates) => coordinates,
+            None => continue,
+        };
+
+        let (uncover, mark, chord) = (
+            input_map.is_bound(InputAction::Uncover, binding),
+            input_map.is_bound(InputAction::Mark, binding),
+            input_map.is_bound(InputAction::Chord, binding),
+        );
+        let is_chord = chord
+            || ((uncover || mark) && both_pressed)
+            || (uncover && !board.is_covered(coordinates));
+        if is_chord {
+            log::info!("Trying to chord a tile at {}", coordinates);
+            board_action_event_writer.send(BoardActionEvent(ReplayAction::Chord(coordinates)));
+        } else if uncover {
+            log::info!("Trying to uncover a tile at {}", coordinates);
+            board_action_event_writer.send(BoardActionEvent(ReplayAction::Trigger(coordinates)));
+        } else if mark {
+            log::info!("Trying to mark a tile at {}", coordinates);
+            board_action_event_writer.send(BoardActionEvent(ReplayAction::Mark(coordinates)));
         }
     }
 }
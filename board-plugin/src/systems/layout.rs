@@ -0,0 +1,120 @@
+use crate::bounds::Bounds2;
+use crate::components::{Coordinates, TileChunk, TileCursor};
+use crate::resources::{Board, BoardOptions, BoardPosition, TileSize};
+use crate::systems::chunks::{chunk_mesh, chunk_translation};
+use crate::systems::startup::board_layout;
+use bevy::log;
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::sprite::Mesh2dHandle;
+use bevy::window::{WindowId, WindowResized};
+
+/// Lays the board out again when the primary window is resized, for adaptive tile sizes and centered boards.
+/// Tiles keep their state, only their size and position change.
+#[allow(clippy::too_many_arguments)]
+pub fn relayout_board(
+    windows: Res<Windows>,
+    board_options: Option<Res<BoardOptions>>,
+    mut board: ResMut<Board>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut window_resized_event_reader: EventReader<WindowResized>,
+    children: Query<&Children>,
+    tiles: Query<&Coordinates>,
+    chunks: Query<(&TileChunk, Option<&Mesh2dHandle>)>,
+    cursors: Query<(), With<TileCursor>>,
+    mut transforms: Query<&mut Transform>,
+    mut sprites: Query<&mut Sprite>,
+    mut texts: Query<&mut Text>,
+) {
+    if !window_resized_event_reader
+        .iter()
+        .any(|event| event.id == WindowId::primary())
+    {
+        return;
+    }
+    let options = match board_options {
+        None => Default::default(),
+        Some(options) => options.clone(),
+    };
+    let is_adaptive = matches!(options.tile_size, TileSize::Adaptive { .. });
+    let is_centered = matches!(options.position, BoardPosition::Centered { .. });
+    if !is_adaptive && !is_centered {
+        return;
+    }
+
+    let (tile_size, board_size, board_position) = board_layout(
+        &options,
+        &windows,
+        (board.tile_map.width, board.tile_map.height),
+    );
+    log::info!("Window resized, board size: {}", board_size);
+    board.tile_size = tile_size;
+    board.bounds = Bounds2 {
+        position: board_position.xy(),
+        size: board_size,
+    };
+    if is_centered {
+        if let Ok(mut transform) = transforms.get_mut(board.entity) {
+            transform.translation = board_position;
+        }
+    }
+
+    let sprite_size = Vec2::splat(board.tile_sprite_size());
+    let marked = board.marked_tiles.iter().copied().collect();
+    let board_children = children
+        .get(board.entity)
+        .into_iter()
+        .flat_map(|children| children.iter());
+    for &child in board_children {
+        if let Ok(coordinates) = tiles.get(child) {
+            if let Ok(mut transform) = transforms.get_mut(child) {
+                transform.translation.x = (coordinates.x as f32 + 0.5) * tile_size;
+                transform.translation.y = (coordinates.y as f32 + 0.5) * tile_size;
+            }
+            if let Ok(mut sprite) = sprites.get_mut(child) {
+                sprite.custom_size = Some(sprite_size);
+            }
+            // Cover and content sprites, then flags on top of covers
+            for &tile_child in children
+                .get(child)
+                .into_iter()
+                .flat_map(|children| children.iter())
+            {
+                if let Ok(mut sprite) = sprites.get_mut(tile_child) {
+                    sprite.custom_size = Some(sprite_size);
+                }
+                if let Ok(mut text) = texts.get_mut(tile_child) {
+                    for section in text.sections.iter_mut() {
+                        section.style.font_size = sprite_size.x;
+                    }
+                }
+                for &flag in children
+                    .get(tile_child)
+                    .into_iter()
+                    .flat_map(|children| children.iter())
+                {
+                    if let Ok(mut sprite) = sprites.get_mut(flag) {
+                        sprite.custom_size = Some(Vec2::splat(tile_size));
+                    }
+                }
+            }
+        } else if let Ok((chunk, mesh)) = chunks.get(child) {
+            // Chunks without mesh yet are built with the new layout
+            if let Some(mesh) = mesh.and_then(|mesh| meshes.get_mut(&mesh.0)) {
+                *mesh = chunk_mesh(&board, &marked, chunk);
+            }
+            if let Ok(mut transform) = transforms.get_mut(child) {
+                transform.translation = chunk_translation(&board, chunk);
+            }
+        } else if cursors.get(child).is_ok() {
+            // The cursor follows the board on its own
+        } else if let Ok(mut sprite) = sprites.get_mut(child) {
+            // Board background
+            sprite.custom_size = Some(board_size);
+            if let Ok(mut transform) = transforms.get_mut(child) {
+                transform.translation.x = board_size.x / 2.;
+                transform.translation.y = board_size.y / 2.;
+            }
+        }
+    }
+}
@@ -1,51 +1,94 @@
 use crate::assets::board::BoardAssets;
-use crate::events::TileMarkEvent;
-use crate::resources::Board;
-use bevy::log;
+use crate::components::Coordinates;
+use crate::events::{TileFlaggedEvent, TileMarkEvent};
+use crate::resources::{Board, BoardHistory, TileMark};
 use bevy::prelude::*;
 
 pub fn mark_tiles(
     mut commands: Commands,
     mut board: ResMut<Board>,
+    mut history: Option<ResMut<BoardHistory>>,
     board_assets: Res<BoardAssets>,
     mut tile_mark_event_reader: EventReader<TileMarkEvent>,
+    mut tile_flagged_event_writer: EventWriter<TileFlaggedEvent>,
     query: Query<&Children>,
 ) {
-    let tile_size = board.tile_size;
-    for (entity, is_marked_now) in tile_mark_event_reader
-        .iter()
-        .filter_map(|event| board.toggle_mark(event.0))
-    {
-        if is_marked_now {
-            commands.entity(entity).with_children(|child_builder| {
-                child_builder.spawn_bundle(SpriteBundle {
-                    sprite: Sprite {
-                        color: board_assets.flag_material.color,
-                        custom_size: Some(Vec2::splat(tile_size)),
-                        ..Default::default()
-                    },
-                    transform: Transform::from_xyz(0., 0., 1.),
-                    texture: board_assets.flag_material.texture.clone(),
-                    ..Default::default()
-                });
-            });
-        } else {
-            // Note: This query could be optimized with a new TileCover component,
-            // therefore avoiding querying every entity with children
-
-            let children = match query.get(entity) {
-                Ok(children) => children,
-                Err(err) => {
-                    log::error!(
-                        "Tried to remove tile mark from entity without children: {}",
-                        err
-                    );
-                    continue;
-                }
-            };
-            for &child in children.iter() {
-                commands.entity(child).despawn_recursive();
+    for event in tile_mark_event_reader.iter() {
+        let previous = board.mark_at(event.0);
+        let mark = match board.toggle_mark(event.0) {
+            Some(mark) => mark,
+            None => continue,
+        };
+        if let Some(history) = history.as_mut() {
+            history.push();
+            if let Some(current) = history.current() {
+                current.marked = Some((event.0, previous, mark));
             }
         }
+        tile_flagged_event_writer.send(TileFlaggedEvent {
+            coordinates: event.0,
+            flagged: mark == Some(TileMark::Flag),
+            questioned: mark == Some(TileMark::Question),
+        });
+        // Flags are only put on unmarked tiles, any other mark replaces the previous one
+        if mark != Some(TileMark::Flag) {
+            despawn_mark(&mut commands, event.0, &board, &query);
+        }
+        if let Some(mark) = mark {
+            spawn_mark(&mut commands, event.0, mark, &board_assets, &board);
+        }
+    }
+}
+
+/// Despawns the flag or question mark sprite on top of the cover of a tile, if any
+pub(crate) fn despawn_mark(
+    commands: &mut Commands,
+    coordinates: Coordinates,
+    board: &Board,
+    covers: &Query<&Children>,
+) {
+    let cover = match board.covers.get(&coordinates) {
+        Some(&cover) => cover,
+        // Marks of chunked boards are drawn by their chunk
+        None => return,
+    };
+    for &child in covers
+        .get(cover)
+        .into_iter()
+        .flat_map(|children| children.iter())
+    {
+        commands.entity(child).despawn_recursive();
     }
 }
+
+/// Spawns the flag or question mark sprite of a marked tile on top of its cover.
+/// Marks of chunked boards are drawn by their chunk instead.
+pub(crate) fn spawn_mark(
+    commands: &mut Commands,
+    coordinates: Coordinates,
+    mark: TileMark,
+    board_assets: &BoardAssets,
+    board: &Board,
+) {
+    let cover = match board.covers.get(&coordinates) {
+        Some(&cover) => cover,
+        None => return,
+    };
+    let material = match mark {
+        TileMark::Flag => &board_assets.flag_material,
+        TileMark::Question => &board_assets.question_material,
+    };
+    let tile_size = board.tile_size;
+    commands.entity(cover).with_children(|child_builder| {
+        child_builder.spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: material.color,
+                custom_size: Some(Vec2::splat(tile_size)),
+                ..Default::default()
+            },
+            transform: Transform::from_xyz(0., 0., 1.),
+            texture: material.texture.clone(),
+            ..Default::default()
+        });
+    });
+}
@@ -1,6 +1,21 @@
 pub use input::*;
 
 mod input;
+pub mod action;
+pub mod camera;
+pub mod chunks;
+pub mod cursor;
+pub mod gamepad;
+pub mod hint;
+pub mod history;
+pub mod layout;
+pub mod placement;
+pub mod puzzle;
+pub mod replay;
+pub mod reveal;
+pub mod stats;
 pub mod startup;
+pub mod touch;
+mod ui;
 pub mod uncover;
 pub mod mark;
@@ -0,0 +1,64 @@
+use crate::assets::board::BoardAssets;
+use crate::components::Coordinates;
+use crate::events::{BoardGeneratedEvent, GenerationFailedEvent, TileTriggerEvent};
+use crate::resources::{Board, TileMap};
+use crate::systems::startup::insert_tile_content;
+use bevy::log;
+use bevy::prelude::*;
+
+/// Places the mines of a board with deferred placement around the first triggered tile
+pub fn deferred_mine_placement(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    board_assets: Res<BoardAssets>,
+    mut tile_trigger_event_reader: EventReader<TileTriggerEvent>,
+    mut board_generated_event_writer: EventWriter<BoardGeneratedEvent>,
+    mut generation_failed_event_writer: EventWriter<GenerationFailedEvent>,
+    tiles: Query<(Entity, &Coordinates)>,
+) {
+    let placement = match board.deferred_placement {
+        Some(placement) => placement,
+        None => return,
+    };
+    let first_click = match tile_trigger_event_reader
+        .iter()
+        .find(|event| board.is_uncoverable(event.0))
+    {
+        Some(event) => event.0,
+        None => return,
+    };
+
+    let safe_tiles = board
+        .tile_map
+        .area_at(first_click, placement.safe_area.radius());
+    let mut rng = TileMap::seeded_rng(board.seed);
+    if let Err(error) = board.tile_map.generate(
+        placement.mine_count,
+        placement.generator,
+        &safe_tiles,
+        Some(first_click),
+        &mut rng,
+    ) {
+        log::error!("{}", error);
+        generation_failed_event_writer.send(GenerationFailedEvent {
+            error,
+            seed: board.seed,
+        });
+    }
+    board.deferred_placement = None;
+    board_generated_event_writer.send(board.generated_event());
+    log::info!(
+        "Placed mines around first uncovered tile at {}",
+        first_click
+    );
+    #[cfg(feature = "debug")]
+    log::info!("{}", board.tile_map.console_output());
+
+    // Chunked boards have no tile entities
+    let size = board.tile_sprite_size();
+    for (entity, coordinates) in tiles.iter() {
+        if let Some(&tile) = board.tile_map.get(*coordinates) {
+            insert_tile_content(&mut commands.entity(entity), tile, &board_assets, size);
+        }
+    }
+}
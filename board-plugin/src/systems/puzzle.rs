@@ -0,0 +1,52 @@
+use crate::assets::{board::BoardAssets, puzzle::Puzzle};
+use crate::events::{BoardGeneratedEvent, GenerationFailedEvent};
+use crate::resources::{Board, BoardOptions, BoardSource};
+use crate::systems::startup::{puzzle_snapshot, spawn_board};
+use bevy::log;
+use bevy::prelude::*;
+
+/// Rebuilds the board whenever its puzzle file is modified
+#[allow(clippy::too_many_arguments)]
+pub fn reload_puzzle(
+    mut commands: Commands,
+    board: Res<Board>,
+    board_options: Option<Res<BoardOptions>>,
+    puzzles: Res<Assets<Puzzle>>,
+    windows: Res<Windows>,
+    board_assets: Res<BoardAssets>,
+    mut puzzle_event_reader: EventReader<AssetEvent<Puzzle>>,
+    mut board_generated_event_writer: EventWriter<BoardGeneratedEvent>,
+    mut generation_failed_event_writer: EventWriter<GenerationFailedEvent>,
+) {
+    let changed: Vec<_> = puzzle_event_reader
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { handle } => Some(handle),
+            AssetEvent::Created { .. } | AssetEvent::Removed { .. } => None,
+        })
+        .collect();
+    let options = match board_options {
+        Some(options) => options,
+        None => return,
+    };
+    match &options.source {
+        BoardSource::Puzzle(handle) if changed.contains(&handle) => (),
+        _ => return,
+    }
+    let snapshot = match puzzle_snapshot(&options, &puzzles) {
+        Some(snapshot) => snapshot,
+        None => return,
+    };
+
+    log::info!("Puzzle changed, rebuilding the board");
+    commands.entity(board.entity).despawn_recursive();
+    spawn_board(
+        &mut commands,
+        &options,
+        Some(snapshot),
+        &windows,
+        &board_assets,
+        &mut board_generated_event_writer,
+        &mut generation_failed_event_writer,
+    );
+}
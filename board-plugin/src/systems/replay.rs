@@ -0,0 +1,150 @@
+use crate::events::BoardActionEvent;
+use crate::resources::{
+    Board, BoardOptions, BoardSource, Replay, ReplayEntry, ReplayPlayer, ReplayRecorder,
+};
+use bevy::log;
+use bevy::prelude::*;
+
+/// Whether the board is built from a puzzle, which replays can not reproduce
+fn is_puzzle(board_options: &Option<Res<BoardOptions>>) -> bool {
+    matches!(
+        board_options.as_deref().map(|options| &options.source),
+        Some(BoardSource::Puzzle(_))
+    )
+}
+
+/// Starts a new recording for the created board
+pub fn reset_recorder(
+    board_options: Option<Res<BoardOptions>>,
+    recorder: Option<ResMut<ReplayRecorder>>,
+) {
+    if let Some(mut recorder) = recorder {
+        *recorder = ReplayRecorder::default();
+        if is_puzzle(&board_options) {
+            log::error!("Puzzle boards can not be replayed, the game is not recorded");
+        }
+    }
+}
+
+/// Records the player actions of the current board, in the order they were sent.
+/// Puzzle boards are not recorded.
+pub fn record_actions(
+    time: Res<Time>,
+    board: Res<Board>,
+    board_options: Option<Res<BoardOptions>>,
+    recorder: Option<ResMut<ReplayRecorder>>,
+    mut board_action_event_reader: EventReader<BoardActionEvent>,
+) {
+    let actions: Vec<_> = board_action_event_reader
+        .iter()
+        .map(|event| event.0)
+        .collect();
+    let mut recorder = match recorder {
+        Some(recorder) if !is_puzzle(&board_options) => recorder,
+        _ => return,
+    };
+    recorder.elapsed += time.delta();
+    let elapsed = recorder.elapsed;
+    let replay = recorder.replay.get_or_insert_with(|| {
+        let options = board_options.map_or_else(Default::default, |options| options.clone());
+        Replay::new(board.seed, options)
+    });
+    for action in actions {
+        log::debug!("Recording {:?}", action);
+        replay.actions.push(ReplayEntry { elapsed, action });
+    }
+}
+
+/// Sends the actions of the replay being played back.
+/// Replays are stopped on puzzle boards, which they were not recorded on.
+pub fn play_actions(
+    mut commands: Commands,
+    time: Res<Time>,
+    board: Res<Board>,
+    board_options: Option<Res<BoardOptions>>,
+    player: Option<ResMut<ReplayPlayer>>,
+    mut board_action_event_writer: EventWriter<BoardActionEvent>,
+) {
+    let mut player = match player {
+        Some(player) => player,
+        None => return,
+    };
+    if is_puzzle(&board_options) {
+        log::error!("Replays can not be played back on puzzle boards");
+        commands.remove_resource::<ReplayPlayer>();
+        return;
+    }
+    // Tiles are still being uncovered while an empty area propagates
+    let settled = !board.is_uncovering();
+    if let Some(action) = player.advance(time.delta(), settled) {
+        board_action_event_writer.send(BoardActionEvent(action));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::Coordinates;
+    use crate::resources::{test_board, PlaybackSpeed, ReplayAction, TileMap};
+
+    #[test]
+    fn records_actions_in_sent_order() {
+        let mut world = World::new();
+        world.insert_resource(Time::default());
+        world.insert_resource(test_board(TileMap::empty(4, 4)));
+        world.insert_resource(ReplayRecorder::default());
+        world.insert_resource(Events::<BoardActionEvent>::default());
+        let actions = [
+            ReplayAction::Mark(Coordinates { x: 1, y: 1 }),
+            ReplayAction::Trigger(Coordinates { x: 2, y: 3 }),
+            ReplayAction::Undo,
+            ReplayAction::Chord(Coordinates { x: 2, y: 3 }),
+        ];
+        let mut events = world
+            .get_resource_mut::<Events<BoardActionEvent>>()
+            .unwrap();
+        for action in actions {
+            events.send(BoardActionEvent(action));
+        }
+
+        SystemStage::single_threaded()
+            .with_system(record_actions)
+            .run(&mut world);
+        let recorder = world.get_resource::<ReplayRecorder>().unwrap();
+        let recorded: Vec<_> = recorder
+            .replay()
+            .unwrap()
+            .actions
+            .iter()
+            .map(|entry| entry.action)
+            .collect();
+        assert_eq!(recorded, actions);
+    }
+
+    #[test]
+    fn skips_puzzle_boards() {
+        let mut world = World::new();
+        world.insert_resource(Time::default());
+        world.insert_resource(test_board(TileMap::empty(4, 4)));
+        world.insert_resource(BoardOptions {
+            source: BoardSource::Puzzle(Handle::default()),
+            ..Default::default()
+        });
+        world.insert_resource(ReplayRecorder::default());
+        world.insert_resource(ReplayPlayer::new(
+            Replay::new(0, BoardOptions::default()),
+            PlaybackSpeed::RealTime,
+        ));
+        let mut events = Events::<BoardActionEvent>::default();
+        events.send(BoardActionEvent(ReplayAction::Undo));
+        world.insert_resource(events);
+
+        SystemStage::single_threaded()
+            .with_system(record_actions)
+            .with_system(play_actions)
+            .run(&mut world);
+        let recorder = world.get_resource::<ReplayRecorder>().unwrap();
+        assert!(recorder.replay().is_none());
+        assert!(world.get_resource::<ReplayPlayer>().is_none());
+    }
+}
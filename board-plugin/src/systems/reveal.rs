@@ -0,0 +1,247 @@
+use crate::assets::board::BoardAssets;
+use crate::components::Coordinates;
+use crate::events::{
+    BoardCompletedEvent, ExplosionUndoneEvent, MineExplodedEvent, TileFlaggedEvent,
+};
+use crate::resources::{Board, TileMark};
+use crate::systems::mark::{despawn_mark, spawn_mark};
+use bevy::log;
+use bevy::prelude::*;
+
+/// Reveals the board once a mine exploded:
+/// remaining mines are shown, the exploded mine is highlighted and wrong flags are marked.
+/// Question marks on mines are despawned, they would be left over the mine by their hidden cover.
+#[allow(clippy::too_many_arguments)]
+pub fn reveal_board(
+    mut commands: Commands,
+    board: Res<Board>,
+    board_assets: Res<BoardAssets>,
+    mut mine_exploded_event_reader: EventReader<MineExplodedEvent>,
+    mut tiles: Query<(&Coordinates, &mut Sprite, &mut Handle<Image>)>,
+    mut covers: Query<(&mut Visibility, Option<&Children>)>,
+    mut flags: Query<(&mut Sprite, &mut Handle<Image>), Without<Coordinates>>,
+    cover_children: Query<&Children>,
+) {
+    let exploded_coordinates = match mine_exploded_event_reader.iter().last() {
+        Some(event) => event.coordinates,
+        None => return,
+    };
+    // Chunks reveal their own tiles
+    if board.chunk_size.is_some() {
+        return;
+    }
+    log::info!("Revealing board");
+
+    if let Some((_, mut sprite, mut texture)) = tiles
+        .iter_mut()
+        .find(|(coordinates, _, _)| **coordinates == exploded_coordinates)
+    {
+        sprite.color = board_assets.exploded_mine_material.color;
+        *texture = board_assets.exploded_mine_material.texture.clone();
+    }
+
+    for (&coordinates, &cover) in board.covers.iter() {
+        let is_mine = board.tile_map.is_mine_at(coordinates);
+        let is_marked = board.is_marked(coordinates);
+        let (mut visibility, children) = match covers.get_mut(cover) {
+            Ok(cover) => cover,
+            Err(error) => {
+                log::error!("{}", error);
+                continue;
+            }
+        };
+
+        if is_mine && !is_marked {
+            visibility.is_visible = false;
+            if board.is_questioned(coordinates) {
+                despawn_mark(&mut commands, coordinates, &board, &cover_children);
+            }
+        } else if !is_mine && is_marked {
+            for &child in children.into_iter().flat_map(|children| children.iter()) {
+                if let Ok((mut sprite, mut texture)) = flags.get_mut(child) {
+                    sprite.color = board_assets.wrong_flag_material.color;
+                    *texture = board_assets.wrong_flag_material.texture.clone();
+                }
+            }
+        }
+    }
+}
+
+/// Hides the board again once the explosion that revealed it is undone,
+/// putting the question marks despawned by `reveal_board` back
+pub fn conceal_board(
+    mut commands: Commands,
+    board: Res<Board>,
+    board_assets: Res<BoardAssets>,
+    mut explosion_undone_event_reader: EventReader<ExplosionUndoneEvent>,
+    mut tiles: Query<(&Coordinates, &mut Sprite, &mut Handle<Image>)>,
+    mut covers: Query<(&mut Visibility, Option<&Children>)>,
+    mut flags: Query<(&mut Sprite, &mut Handle<Image>), Without<Coordinates>>,
+) {
+    let exploded_coordinates = match explosion_undone_event_reader.iter().last() {
+        Some(event) => event.coordinates,
+        None => return,
+    };
+    // Chunks conceal their own tiles
+    if board.chunk_size.is_some() {
+        return;
+    }
+    log::info!("Concealing board");
+
+    if let Some((_, mut sprite, mut texture)) = tiles
+        .iter_mut()
+        .find(|(coordinates, _, _)| **coordinates == exploded_coordinates)
+    {
+        sprite.color = board_assets.tile_material.color;
+        *texture = board_assets.tile_material.texture.clone();
+    }
+
+    for (&coordinates, &cover) in board.covers.iter() {
+        // Covers spawned by the undo are not there yet, and visible anyway
+        let (mut visibility, children) = match covers.get_mut(cover) {
+            Ok(cover) => cover,
+            Err(_) => continue,
+        };
+        visibility.is_visible = true;
+        if board.is_questioned(coordinates) && board.tile_map.is_mine_at(coordinates) {
+            spawn_mark(
+                &mut commands,
+                coordinates,
+                TileMark::Question,
+                &board_assets,
+                &board,
+            );
+        } else if board.is_marked(coordinates) {
+            for &child in children.into_iter().flat_map(|children| children.iter()) {
+                if let Ok((mut sprite, mut texture)) = flags.get_mut(child) {
+                    sprite.color = board_assets.flag_material.color;
+                    *texture = board_assets.flag_material.texture.clone();
+                }
+            }
+        }
+    }
+}
+
+/// Flags every mine left covered once the board is completed, replacing question marks
+pub fn flag_remaining_mines(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    board_assets: Res<BoardAssets>,
+    mut board_completed_event_reader: EventReader<BoardCompletedEvent>,
+    mut tile_flagged_event_writer: EventWriter<TileFlaggedEvent>,
+    covers: Query<&Children>,
+) {
+    if board_completed_event_reader.iter().last().is_none() {
+        return;
+    }
+
+    let unmarked: Vec<_> = board
+        .covered_tiles()
+        .filter(|coordinates| !board.is_marked(*coordinates))
+        .collect();
+    for coordinates in unmarked {
+        let questioned = board.is_questioned(coordinates);
+        if board.set_mark(coordinates, Some(TileMark::Flag)) {
+            if questioned {
+                despawn_mark(&mut commands, coordinates, &board, &covers);
+            }
+            spawn_mark(
+                &mut commands,
+                coordinates,
+                TileMark::Flag,
+                &board_assets,
+                &board,
+            );
+            tile_flagged_event_writer.send(TileFlaggedEvent {
+                coordinates,
+                flagged: true,
+                questioned: false,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assets::SpriteMaterial;
+    use crate::resources::{test_board, TileMap};
+    use std::time::Duration;
+
+    fn board_assets() -> BoardAssets {
+        BoardAssets {
+            label: String::new(),
+            board_material: SpriteMaterial::default(),
+            tile_material: SpriteMaterial::default(),
+            covered_tile_material: SpriteMaterial::default(),
+            mine_material: SpriteMaterial::default(),
+            flag_material: SpriteMaterial::default(),
+            question_material: SpriteMaterial {
+                color: Color::BLUE,
+                ..Default::default()
+            },
+            exploded_mine_material: SpriteMaterial::default(),
+            wrong_flag_material: SpriteMaterial::default(),
+            cursor_material: SpriteMaterial::default(),
+            neighbor_font: Default::default(),
+            mine_counter_colors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn conceals_question_marks_on_mines() {
+        let questioned = Coordinates { x: 0, y: 0 };
+        let exploded = Coordinates { x: 1, y: 0 };
+        let mut world = World::new();
+        let mark = world.spawn().insert_bundle(SpriteBundle::default()).id();
+        let cover = world
+            .spawn()
+            .insert_bundle(SpriteBundle::default())
+            .push_children(&[mark])
+            .id();
+        let mut board = Board {
+            questioned_tiles: vec![questioned],
+            ..test_board(TileMap::from_mines(2, 1, [questioned, exploded]))
+        };
+        board.cover_tile(questioned);
+        board.covers.insert(questioned, cover);
+        world.insert_resource(board);
+        world.insert_resource(board_assets());
+        world.insert_resource(Events::<MineExplodedEvent>::default());
+        world.insert_resource(Events::<ExplosionUndoneEvent>::default());
+        let children = |world: &World| {
+            world
+                .get::<Children>(cover)
+                .map_or(Vec::new(), |children| children.to_vec())
+        };
+
+        world
+            .get_resource_mut::<Events<MineExplodedEvent>>()
+            .unwrap()
+            .send(MineExplodedEvent {
+                coordinates: exploded,
+                elapsed: Duration::ZERO,
+                clicks: 1,
+                seed: 0,
+            });
+        SystemStage::single_threaded()
+            .with_system(reveal_board)
+            .run(&mut world);
+        assert!(!world.get::<Visibility>(cover).unwrap().is_visible);
+        assert!(children(&world).is_empty());
+
+        world
+            .get_resource_mut::<Events<ExplosionUndoneEvent>>()
+            .unwrap()
+            .send(ExplosionUndoneEvent {
+                coordinates: exploded,
+            });
+        SystemStage::single_threaded()
+            .with_system(conceal_board)
+            .run(&mut world);
+        assert!(world.get::<Visibility>(cover).unwrap().is_visible);
+        let marks = children(&world);
+        assert_eq!(marks.len(), 1);
+        assert_eq!(world.get::<Sprite>(marks[0]).unwrap().color, Color::BLUE);
+    }
+}
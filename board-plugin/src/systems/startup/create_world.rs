@@ -1,59 +1,135 @@
 use crate::assets::board::BoardAssets;
+use crate::assets::puzzle::Puzzle;
 use crate::bounds::Bounds2;
 use crate::components::*;
+use crate::events::{BoardGeneratedEvent, GenerationFailedEvent};
 use crate::resources::*;
+use crate::systems::mark::spawn_mark;
+use bevy::asset::{HandleId, LoadState};
+use bevy::ecs::system::EntityCommands;
 use bevy::log;
 use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
-use bevy::utils::AHashExt;
 use bevy::utils::HashMap;
+use fixedbitset::FixedBitSet;
 
-/// System to generate the complete Board
+/// System to generate the complete Board while there is none,
+/// or to restore it from a `BoardSnapshot` resource or the puzzle of the `BoardOptions`.
+/// Nothing is spawned until the puzzle is loaded, a board is generated if it fails to load or is no asset to load.
+#[allow(clippy::too_many_arguments)]
 pub fn create_board(
     mut commands: Commands,
+    board: Option<Res<Board>>,
     board_options: Option<Res<BoardOptions>>,
+    snapshot: Option<Res<BoardSnapshot>>,
+    puzzles: Res<Assets<Puzzle>>,
+    asset_server: Res<AssetServer>,
     windows: Res<Windows>,
     board_assets: Res<BoardAssets>,
+    mut board_generated_event_writer: EventWriter<BoardGeneratedEvent>,
+    mut generation_failed_event_writer: EventWriter<GenerationFailedEvent>,
 ) {
+    if board.is_some() {
+        return;
+    }
     let options = match board_options {
         None => Default::default(),
         Some(options) => options.clone(),
     };
-    let mut tile_map = TileMap::empty(options.map_size.0, options.map_size.1);
-    tile_map.place_mines(options.mine_count);
-    #[cfg(feature = "debug")]
-    log::info!("{}", tile_map.console_output());
-
-    let tile_size = match options.tile_size {
-        TileSize::Fixed(size) => size,
-        TileSize::Adaptive { min, max } => {
-            calculate_adaptative_tile_size(windows, (min, max), (tile_map.width, tile_map.height))
+    let snapshot = match snapshot {
+        Some(snapshot) => {
+            log::info!("Resuming board with seed {}", snapshot.seed);
+            commands.remove_resource::<BoardSnapshot>();
+            Some(snapshot.clone())
         }
+        None => puzzle_snapshot(&options, &puzzles),
     };
-
-    let board_size = Vec2::new(
-        tile_map.width as f32 * tile_size,
-        tile_map.height as f32 * tile_size,
+    if let (None, BoardSource::Puzzle(handle)) = (&snapshot, &options.source) {
+        match asset_server.get_load_state(handle) {
+            LoadState::Failed => {
+                log::error!("Failed to load the puzzle, generating a board instead")
+            }
+            // Only handles of the asset server ever load, others would be waited on forever
+            LoadState::NotLoaded if !matches!(handle.id, HandleId::AssetPathId(_)) => {
+                log::error!("The puzzle is not an asset loaded by the asset server, generating a board instead")
+            }
+            _ => return,
+        }
+    }
+    spawn_board(
+        &mut commands,
+        &options,
+        snapshot,
+        &windows,
+        &board_assets,
+        &mut board_generated_event_writer,
+        &mut generation_failed_event_writer,
     );
-    log::info!("Board size: {}", board_size);
+}
 
-    let board_position = match options.position {
-        BoardPosition::Centered { offset } => {
-            // Using the bottom left as anchor position
-            Vec3::new(-(board_size.x / 2.), -(board_size.y / 2.), 0.) + offset
-        }
-        BoardPosition::Custom(position) => position,
+/// Start of the puzzle the board is built from, if any and once it is loaded
+pub(crate) fn puzzle_snapshot(
+    options: &BoardOptions,
+    puzzles: &Assets<Puzzle>,
+) -> Option<BoardSnapshot> {
+    let handle = match &options.source {
+        BoardSource::Generated => return None,
+        BoardSource::Puzzle(handle) => handle,
+    };
+    let puzzle = puzzles.get(handle)?;
+    log::info!("Building board from puzzle {:?}", puzzle.title);
+    Some(puzzle.snapshot())
+}
+
+/// Spawns the board and inserts the `Board` and `BoardStats` resources,
+/// restoring the given snapshot or generating a new board
+pub(crate) fn spawn_board(
+    commands: &mut Commands,
+    options: &BoardOptions,
+    snapshot: Option<BoardSnapshot>,
+    windows: &Windows,
+    board_assets: &BoardAssets,
+    board_generated_event_writer: &mut EventWriter<BoardGeneratedEvent>,
+    generation_failed_event_writer: &mut EventWriter<GenerationFailedEvent>,
+) {
+    let (tile_map, seed, deferred_placement) = match &snapshot {
+        Some(snapshot) => (
+            snapshot.tile_map.clone(),
+            snapshot.seed,
+            snapshot.deferred_placement,
+        ),
+        None => generate_tile_map(options, generation_failed_event_writer),
     };
 
-    let tile_count = (tile_map.width * tile_map.height).into();
-    let mut covered_tiles = HashMap::with_capacity(tile_count);
-    let mut safe_start_entity = None;
+    let (tile_size, board_size, board_position) =
+        board_layout(options, windows, (tile_map.width, tile_map.height));
+    log::info!("Board size: {}", board_size);
+
+    let chunk_size = options
+        .rendering
+        .chunk_size((tile_map.width, tile_map.height));
+    let tile_count = tile_map.width as usize * tile_map.height as usize;
+    let mut covered = FixedBitSet::with_capacity(tile_count);
+    match &snapshot {
+        Some(snapshot) => {
+            for index in snapshot
+                .covered_tiles
+                .iter()
+                .filter_map(|coordinates| tile_map.index_of(*coordinates))
+            {
+                covered.insert(index);
+            }
+        }
+        None => covered.insert_range(..),
+    }
+    let mut covers = HashMap::default();
 
     let board_entity = commands
         .spawn()
         .insert(Name::new("Board"))
         .insert(Transform::from_translation(board_position))
-        .insert(GlobalTransform::default())
+        // Set right away so the board can be picked before transforms are propagated
+        .insert(GlobalTransform::from_translation(board_position))
         .with_children(|parent| {
             // We spawn the board background sprite at the center of the board, since the sprite pivot is centered
             parent
@@ -69,42 +145,175 @@ pub fn create_board(
                 })
                 .insert(Name::new("Board Background"));
 
-            let graphic_options = TileGraphicOptions {
-                size: tile_size,
-                padding: options.tile_padding,
-            };
-            spawn_tiles(
+            match chunk_size {
+                // Chunked boards keep their tiles in the `Board` only
+                Some(chunk_size) => spawn_chunks(parent, &tile_map, chunk_size),
+                None => {
+                    let graphic_options = TileGraphicOptions {
+                        size: tile_size,
+                        padding: options.tile_padding,
+                    };
+                    spawn_tiles(
+                        parent,
+                        &tile_map,
+                        board_assets,
+                        &graphic_options,
+                        |coordinates| {
+                            tile_map
+                                .index_of(coordinates)
+                                .map_or(false, |index| covered.contains(index))
+                        },
+                        &mut covers,
+                    );
+                }
+            }
+            spawn_tile_cursor(
                 parent,
-                &tile_map,
-                &board_assets,
-                &graphic_options,
-                &mut covered_tiles,
-                &mut safe_start_entity,
+                board_assets,
+                (tile_map.width, tile_map.height),
+                None,
             );
         })
         .id();
 
-    commands.insert_resource(Board {
+    let mut board = Board {
         tile_map,
         bounds: Bounds2 {
             position: board_position.xy(),
             size: board_size,
         },
         tile_size,
-        covered_tiles,
+        tile_padding: options.tile_padding,
+        covered,
+        covers,
+        uncovering: Vec::new(),
         entity: board_entity,
         marked_tiles: Vec::with_capacity(tile_count),
+        questioned_tiles: Vec::new(),
+        question_marks: options.question_marks,
+        seed,
+        deferred_placement,
+        status: BoardStatus::Playing,
+        exploded: None,
+        started: false,
+        chunk_size,
+    };
+    let mut stats = BoardStats::default();
+    if let Some(snapshot) = snapshot {
+        let marks = snapshot
+            .marked_tiles
+            .iter()
+            .map(|&coordinates| (coordinates, TileMark::Flag))
+            .chain(
+                snapshot
+                    .questioned_tiles
+                    .iter()
+                    .map(|&coordinates| (coordinates, TileMark::Question)),
+            );
+        for (coordinates, mark) in marks {
+            if board.set_mark(coordinates, Some(mark)) {
+                spawn_mark(commands, coordinates, mark, board_assets, &board);
+            }
+        }
+        board.uncovering.extend(
+            snapshot
+                .uncovering_tiles
+                .iter()
+                .map(|&coordinates| (coordinates, None)),
+        );
+        board.status = snapshot.status;
+        board.started = snapshot.started;
+        stats = snapshot.stats;
+    } else if options.safe_start && deferred_placement.is_none() {
+        let safe_start = board.tile_map.first_empty_tile();
+        board
+            .uncovering
+            .extend(safe_start.map(|coordinates| (coordinates, None)));
+    }
+
+    if board.deferred_placement.is_none() {
+        board_generated_event_writer.send(board.generated_event());
+    }
+    stats.remaining_mines = board.remaining_mines();
+    match options.undo {
+        UndoMode::Disabled => commands.remove_resource::<BoardHistory>(),
+        UndoMode::Enabled => commands.insert_resource(BoardHistory::new(false)),
+        UndoMode::Assisted => commands.insert_resource(BoardHistory::new(true)),
+    }
+    commands.insert_resource(stats);
+    commands.insert_resource(board);
+}
+
+/// Generates a new tile map, returning it with its seed and deferred placement
+fn generate_tile_map(
+    options: &BoardOptions,
+    generation_failed_event_writer: &mut EventWriter<GenerationFailedEvent>,
+) -> (TileMap, u64, Option<DeferredPlacement>) {
+    let seed = options.seed.unwrap_or_else(rand::random);
+    log::info!("Generating board with seed {}", seed);
+    let mut tile_map = TileMap::empty(options.map_size.0, options.map_size.1);
+    let mine_count = options.placeable_mine_count();
+    if mine_count < options.mine_count {
+        log::warn!(
+            "Too many mines for a {}x{} board, placing {} instead of {}",
+            options.map_size.0,
+            options.map_size.1,
+            mine_count,
+            options.mine_count
+        );
+    }
+    let generator = options.checked_generator().unwrap_or_else(|error| {
+        log::error!("{}", error);
+        generation_failed_event_writer.send(GenerationFailedEvent { error, seed });
+        Generator::Random
     });
+    let deferred_placement = match options.first_click_safety {
+        Some(safe_area) => Some(DeferredPlacement {
+            mine_count,
+            safe_area,
+            generator,
+        }),
+        None => {
+            let mut rng = TileMap::seeded_rng(seed);
+            if let Err(error) = tile_map.generate(mine_count, generator, &[], None, &mut rng) {
+                log::error!("{}", error);
+                generation_failed_event_writer.send(GenerationFailedEvent { error, seed });
+            }
+            #[cfg(feature = "debug")]
+            log::info!("{}", tile_map.console_output());
+            None
+        }
+    };
+    (tile_map, seed, deferred_placement)
+}
 
-    if options.safe_start {
-        if let Some(entity) = safe_start_entity {
-            commands.entity(entity).insert(Uncovered);
+/// Tile size, board size and board position of a tile map of the given size
+pub(crate) fn board_layout(
+    options: &BoardOptions,
+    windows: &Windows,
+    (width, height): (u16, u16),
+) -> (f32, Vec2, Vec3) {
+    let tile_size = match options.tile_size {
+        TileSize::Fixed(size) => size,
+        TileSize::Adaptive { min, max } => {
+            calculate_adaptative_tile_size(windows, (min, max), (width, height))
         }
-    }
+    };
+
+    let board_size = Vec2::new(width as f32 * tile_size, height as f32 * tile_size);
+
+    let board_position = match options.position {
+        BoardPosition::Centered { offset } => {
+            // Using the bottom left as anchor position
+            Vec3::new(-(board_size.x / 2.), -(board_size.y / 2.), 0.) + offset
+        }
+        BoardPosition::Custom(position) => position,
+    };
+    (tile_size, board_size, board_position)
 }
 
 fn calculate_adaptative_tile_size(
-    windows: Res<Windows>,
+    windows: &Windows,
     (min, max): (f32, f32),
     (width, heigh): (u16, u16),
 ) -> f32 {
@@ -126,26 +335,24 @@ impl TileGraphicOptions {
     }
 }
 
+/// Spawns the sprite entities of the tiles of a board rendered without chunks
 fn spawn_tiles(
     parent: &mut ChildBuilder,
     tile_map: &TileMap,
     assets: &BoardAssets,
     graphic_options: &TileGraphicOptions,
-    covered_tiles: &mut HashMap<Coordinates, Entity>,
-    safe_start_entity: &mut Option<Entity>,
+    is_covered: impl Fn(Coordinates) -> bool,
+    covers: &mut HashMap<Coordinates, Entity>,
 ) {
-    for (y, line) in tile_map.iter().enumerate() {
-        for (x, tile) in line.iter().enumerate() {
-            let coordinates = Coordinates {
-                x: x as u16,
-                y: y as u16,
-            };
-
-            let mut cmd = parent.spawn();
-            cmd.insert_bundle(SpriteBundle {
+    let size = graphic_options.effective_sprite_size();
+    for (coordinates, tile) in tile_map.iter_with_coordinates() {
+        let (x, y) = (coordinates.x, coordinates.y);
+        let mut cmd = parent.spawn();
+        cmd.insert(coordinates)
+            .insert_bundle(SpriteBundle {
                 sprite: Sprite {
                     color: assets.tile_material.color,
-                    custom_size: Some(Vec2::splat(graphic_options.effective_sprite_size())),
+                    custom_size: Some(Vec2::splat(size)),
                     ..Default::default()
                 },
                 transform: Transform::from_xyz(
@@ -157,62 +364,112 @@ fn spawn_tiles(
                 texture: assets.tile_material.texture.clone(),
                 ..Default::default()
             })
-            .insert(Name::new(format!("Tile ({}, {})", x, y)))
-            .insert(coordinates);
+            .insert(Name::new(format!("Tile ({}, {})", x, y)));
 
+        if is_covered(coordinates) {
             cmd.with_children(|parent| {
-                let entity = parent
-                    .spawn_bundle(SpriteBundle {
-                        sprite: Sprite {
-                            custom_size: Some(Vec2::splat(graphic_options.effective_sprite_size())),
-                            color: assets.covered_tile_material.color,
-                            ..Default::default()
-                        },
-                        transform: Transform::from_xyz(0., 0., 2.),
-                        texture: assets.covered_tile_material.texture.clone(),
-                        ..Default::default()
-                    })
-                    .insert(Name::new("Tile Cover"))
-                    .id();
-                covered_tiles.insert(coordinates, entity);
-                if safe_start_entity.is_none() && *tile == Tile::Empty {
-                    *safe_start_entity = Some(entity);
-                }
+                covers.insert(coordinates, spawn_cover(parent, assets, size));
             });
+        }
 
-            match tile {
-                Tile::Mine => {
-                    cmd.insert(Mine).with_children(|parent| {
-                        parent.spawn_bundle(SpriteBundle {
-                            sprite: Sprite {
-                                custom_size: Some(Vec2::splat(
-                                    graphic_options.effective_sprite_size(),
-                                )),
-                                color: assets.mine_material.color,
-                                ..Default::default()
-                            },
-                            transform: Transform::from_xyz(0., 0., 1.),
-                            texture: assets.mine_material.texture.clone(),
-                            ..Default::default()
-                        });
-                    });
-                }
-                Tile::Neighbor(count) => {
-                    cmd.insert(Neighbor { count: *count })
-                        .with_children(|parent| {
-                            parent.spawn_bundle(create_mine_count_text_bundle(
-                                *count,
-                                assets,
-                                graphic_options.effective_sprite_size(),
-                            ));
-                        });
-                }
-                Tile::Empty => (),
-            };
+        insert_tile_content(&mut cmd, *tile, assets, size);
+    }
+}
+
+/// Spawns the cover sprite of a tile
+pub(crate) fn spawn_cover(parent: &mut ChildBuilder, assets: &BoardAssets, size: f32) -> Entity {
+    parent
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(size)),
+                color: assets.covered_tile_material.color,
+                ..Default::default()
+            },
+            transform: Transform::from_xyz(0., 0., 2.),
+            texture: assets.covered_tile_material.texture.clone(),
+            ..Default::default()
+        })
+        .insert(Name::new("Tile Cover"))
+        .id()
+}
+
+/// Spawns a hidden tile cursor in the middle of a board of the given size,
+/// moved by the given gamepad or by the keyboard.
+/// It is placed over its tile once the board is inserted.
+pub(crate) fn spawn_tile_cursor(
+    parent: &mut ChildBuilder,
+    board_assets: &BoardAssets,
+    (width, height): (u16, u16),
+    gamepad: Option<Gamepad>,
+) {
+    parent
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: board_assets.cursor_material.color,
+                ..Default::default()
+            },
+            texture: board_assets.cursor_material.texture.clone(),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(Name::new("Tile Cursor"))
+        .insert(TileCursor {
+            coordinates: Coordinates {
+                x: width / 2,
+                y: height / 2,
+            },
+            gamepad,
+        });
+}
+
+/// Spawns the chunks drawing the tiles of a chunked board
+fn spawn_chunks(parent: &mut ChildBuilder, tile_map: &TileMap, chunk_size: u16) {
+    for y in (0..tile_map.height).step_by(chunk_size as usize) {
+        for x in (0..tile_map.width).step_by(chunk_size as usize) {
+            let origin = Coordinates { x, y };
+            parent
+                .spawn()
+                .insert(TileChunk {
+                    origin,
+                    width: chunk_size.min(tile_map.width - x),
+                    height: chunk_size.min(tile_map.height - y),
+                })
+                .insert(Name::new(format!("Tile Chunk {}", origin)));
         }
     }
 }
 
+/// Inserts the `Mine` or `Neighbor` component of a tile entity and spawns its content sprite
+pub(crate) fn insert_tile_content(
+    cmd: &mut EntityCommands,
+    tile: Tile,
+    assets: &BoardAssets,
+    size: f32,
+) {
+    match tile {
+        Tile::Mine => {
+            cmd.insert(Mine).with_children(|parent| {
+                parent.spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::splat(size)),
+                        color: assets.mine_material.color,
+                        ..Default::default()
+                    },
+                    transform: Transform::from_xyz(0., 0., 1.),
+                    texture: assets.mine_material.texture.clone(),
+                    ..Default::default()
+                });
+            });
+        }
+        Tile::Neighbor(count) => {
+            cmd.insert(Neighbor { count }).with_children(|parent| {
+                parent.spawn_bundle(create_mine_count_text_bundle(count, assets, size));
+            });
+        }
+        Tile::Empty => (),
+    };
+}
+
 /// Generates the mine counter text 2D bundle for a given count
 fn create_mine_count_text_bundle(count: u8, board_assets: &BoardAssets, size: f32) -> Text2dBundle {
     let text = count.to_string();
@@ -0,0 +1,29 @@
+use crate::events::TimerTickedEvent;
+use crate::resources::{Board, BoardStats};
+use bevy::prelude::*;
+
+/// Advances the game timer once the first tile is uncovered, until the game is over
+pub fn tick_timer(
+    time: Res<Time>,
+    board: Res<Board>,
+    mut stats: ResMut<BoardStats>,
+    mut timer_ticked_event_writer: EventWriter<TimerTickedEvent>,
+) {
+    let seconds = stats.elapsed.as_secs();
+    if board.is_playing() && board.is_started() {
+        stats.elapsed += time.delta();
+    }
+    // Resumed games show their time right away
+    if stats.is_added() || stats.elapsed.as_secs() != seconds {
+        timer_ticked_event_writer.send(TimerTickedEvent {
+            elapsed: stats.elapsed,
+        });
+    }
+}
+
+/// Keeps the remaining mine counter in sync with the board marks
+pub fn count_remaining_mines(board: Res<Board>, mut stats: ResMut<BoardStats>) {
+    if board.is_changed() {
+        stats.remaining_mines = board.remaining_mines();
+    }
+}
@@ -0,0 +1,176 @@
+use super::ui::is_over_ui;
+use crate::components::BoardCamera;
+use crate::events::BoardActionEvent;
+use crate::resources::{Board, CameraOptions, ReplayAction, ReplayPlayer, TouchOptions};
+use bevy::log;
+use bevy::prelude::*;
+use bevy::ui::CalculatedClip;
+use bevy::utils::HashMap;
+
+/// Finger held on the screen, becoming a tap when released or a long press when held
+#[derive(Debug, Copy, Clone)]
+pub struct TouchPress {
+    /// Touch position when the finger was put down
+    start: Vec2,
+    /// Seconds the finger was held
+    held: f32,
+    /// Whether the touch already acted, or was dragged or pinched and never acts
+    done: bool,
+}
+
+impl TouchPress {
+    fn new(start: Vec2) -> Self {
+        Self {
+            start,
+            held: 0.,
+            done: false,
+        }
+    }
+
+    /// Holds the touch at `position` for another `delta` seconds, returning whether it becomes a long press
+    fn hold(&mut self, position: Vec2, delta: f32, long_press: f32, drag_threshold: f32) -> bool {
+        if self.done {
+            return false;
+        }
+        if self.start.distance(position) > drag_threshold {
+            self.done = true;
+            return false;
+        }
+        self.held += delta;
+        self.done = self.held >= long_press;
+        self.done
+    }
+
+    /// Lifts the finger, returning whether the touch is a tap
+    fn release(&mut self) -> bool {
+        !std::mem::replace(&mut self.done, true)
+    }
+}
+
+/// Sends tile events for touches on the board, picked through the `BoardCamera`.
+/// Taps uncover tiles or chord uncovered ones and long presses mark tiles, swapped on covered tiles in flag mode.
+/// Touches dragged further than the camera drag threshold or pinching do nothing,
+/// neither do touches started over UI nodes with an `Interaction`, such as buttons.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn touch_input(
+    time: Res<Time>,
+    windows: Res<Windows>,
+    board: Res<Board>,
+    touches: Res<Touches>,
+    options: Res<TouchOptions>,
+    camera_options: Res<CameraOptions>,
+    replay_player: Option<Res<ReplayPlayer>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<BoardCamera>>,
+    transforms: Query<&GlobalTransform>,
+    ui_nodes: Query<(&Node, &GlobalTransform, Option<&CalculatedClip>), With<Interaction>>,
+    mut presses: Local<HashMap<u64, TouchPress>>,
+    mut board_action_event_writer: EventWriter<BoardActionEvent>,
+) {
+    if !board.is_playing() || replay_player.is_some() {
+        presses.clear();
+        return;
+    }
+
+    for touch in touches.iter_just_pressed() {
+        if is_over_ui(touch.position(), ui_nodes.iter()) {
+            log::debug!("Ignoring touch {} over the UI", touch.id());
+            continue;
+        }
+        presses.insert(touch.id(), TouchPress::new(touch.position()));
+    }
+    // Pinching zooms the camera instead
+    if touches.iter().count() > 1 {
+        presses.values_mut().for_each(|press| press.done = true);
+    }
+    let mut actions = Vec::new();
+    for touch in touches.iter() {
+        if let Some(press) = presses.get_mut(&touch.id()) {
+            let delta = time.delta_seconds();
+            if press.hold(
+                touch.position(),
+                delta,
+                options.long_press,
+                camera_options.drag_threshold,
+            ) {
+                actions.push((touch.position(), true));
+            }
+        }
+    }
+    for touch in touches.iter_just_released() {
+        if let Some(mut press) = presses.remove(&touch.id()) {
+            if press.release() {
+                actions.push((touch.position(), false));
+            }
+        }
+    }
+    for touch in touches.iter_just_cancelled() {
+        presses.remove(&touch.id());
+    }
+    if actions.is_empty() {
+        return;
+    }
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let (camera, camera_transform) = match cameras.iter().next() {
+        Some(camera) => camera,
+        None => {
+            log::warn!("No `BoardCamera` found to pick tiles through");
+            return;
+        }
+    };
+    let board_transform = match transforms.get(board.entity) {
+        Ok(transform) => transform,
+        Err(error) => {
+            log::error!("{}", error);
+            return;
+        }
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+    for (position, long_press) in actions {
+        let ray = BoardCamera::screen_to_ray(position, window_size, camera, camera_transform);
+        let coordinates = match board.pick_tile(board_transform, ray) {
+            Some(coordinates) => coordinates,
+            None => continue,
+        };
+        if !board.is_covered(coordinates) {
+            // Long presses on numbers are left alone, nothing to mark there
+            if !long_press {
+                log::info!("Trying to chord a tile at {}", coordinates);
+                board_action_event_writer.send(BoardActionEvent(ReplayAction::Chord(coordinates)));
+            }
+        } else if long_press != options.flag_mode {
+            log::info!("Trying to mark a tile at {}", coordinates);
+            board_action_event_writer.send(BoardActionEvent(ReplayAction::Mark(coordinates)));
+        } else {
+            log::info!("Trying to uncover a tile at {}", coordinates);
+            board_action_event_writer.send(BoardActionEvent(ReplayAction::Trigger(coordinates)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn taps_long_presses_and_drags() {
+        let mut tap = TouchPress::new(Vec2::ZERO);
+        assert!(!tap.hold(Vec2::new(2., 0.), 0.25, 0.5, 5.));
+        assert!(tap.release());
+
+        let mut long_press = TouchPress::new(Vec2::ZERO);
+        assert!(!long_press.hold(Vec2::ZERO, 0.25, 0.5, 5.));
+        assert!(long_press.hold(Vec2::ZERO, 0.25, 0.5, 5.));
+        // Acts only once
+        assert!(!long_press.hold(Vec2::ZERO, 0.25, 0.5, 5.));
+        assert!(!long_press.release());
+
+        let mut drag = TouchPress::new(Vec2::ZERO);
+        assert!(!drag.hold(Vec2::new(0., 10.), 0.25, 0.5, 5.));
+        assert!(!drag.hold(Vec2::ZERO, 0.5, 0.5, 5.));
+        assert!(!drag.release());
+    }
+}
@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+use bevy::ui::CalculatedClip;
+
+/// Whether `position` lies over one of the UI `nodes`, clipped like the UI focus system does
+pub(super) fn is_over_ui<'a>(
+    position: Vec2,
+    nodes: impl IntoIterator<Item = (&'a Node, &'a GlobalTransform, Option<&'a CalculatedClip>)>,
+) -> bool {
+    nodes.into_iter().any(|(node, transform, clip)| {
+        let center = transform.translation.truncate();
+        let (mut min, mut max) = (center - node.size / 2., center + node.size / 2.);
+        if let Some(clip) = clip {
+            min = min.max(clip.clip.min);
+            max = max.min(clip.clip.max);
+        }
+        (min.x..max.x).contains(&position.x) && (min.y..max.y).contains(&position.y)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn finds_positions_over_ui() {
+        let node = Node {
+            size: Vec2::new(40., 20.),
+        };
+        let transform = GlobalTransform::from_translation(Vec3::new(100., 50., 0.));
+        let clip = CalculatedClip {
+            clip: bevy::sprite::Rect {
+                min: Vec2::new(90., 0.),
+                max: Vec2::new(200., 100.),
+            },
+        };
+        assert!(is_over_ui(Vec2::new(85., 55.), [(&node, &transform, None)]));
+        assert!(!is_over_ui(
+            Vec2::new(85., 65.),
+            [(&node, &transform, None)]
+        ));
+        assert!(!is_over_ui(
+            Vec2::new(85., 55.),
+            [(&node, &transform, Some(&clip))]
+        ));
+    }
+}
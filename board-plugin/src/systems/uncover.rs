@@ -1,64 +1,120 @@
-use crate::components::*;
-use crate::events::{BoardCompletedEvent, MineExplodedEvent, TileTriggerEvent};
-use crate::resources::Board;
+use crate::events::{
+    BoardCompletedEvent, MineExplodedEvent, TileChordEvent, TileTriggerEvent, TileUncoveredEvent,
+};
+use crate::resources::{Board, BoardHistory, BoardStats, BoardStatus, Tile};
 use bevy::log;
 use bevy::prelude::*;
 
 pub fn trigger_event_handler(
-    mut commands: Commands,
-    board: Res<Board>,
+    mut board: ResMut<Board>,
+    mut history: Option<ResMut<BoardHistory>>,
     mut tile_trigger_event_reader: EventReader<TileTriggerEvent>,
 ) {
-    for entity in tile_trigger_event_reader
-        .iter()
-        .filter_map(|event| board.get_covered_tile(event.0))
-    {
-        commands.entity(entity).insert(Uncovered);
+    for event in tile_trigger_event_reader.iter() {
+        if board.is_uncoverable(event.0) {
+            let id = history.as_mut().map(|history| history.push());
+            board.uncovering.push((event.0, id));
+            board.started = true;
+        }
+    }
+}
+
+pub fn chord_event_handler(
+    mut board: ResMut<Board>,
+    mut history: Option<ResMut<BoardHistory>>,
+    mut tile_chord_event_reader: EventReader<TileChordEvent>,
+) {
+    for event in tile_chord_event_reader.iter() {
+        let tiles = match board.get_chord_tiles(event.0) {
+            Some(tiles) if !tiles.is_empty() => tiles,
+            _ => continue,
+        };
+        let id = history.as_mut().map(|history| history.push());
+        board
+            .uncovering
+            .extend(tiles.into_iter().map(|coordinates| (coordinates, id)));
+        board.started = true;
     }
 }
 
+/// Uncovers the tiles waiting in the `Board`, empty tiles queue their neighbors for the next frame
+#[allow(clippy::too_many_arguments)]
 pub fn uncover_tiles(
     mut commands: Commands,
     mut board: ResMut<Board>,
-    // Entities in covered_tiles, which have the sprite component
-    children: Query<(Entity, &Parent), With<Uncovered>>,
-    parents: Query<(&Coordinates, Option<&Mine>, Option<&Neighbor>)>,
+    stats: Res<BoardStats>,
+    mut history: Option<ResMut<BoardHistory>>,
     mut board_completed_event_writer: EventWriter<BoardCompletedEvent>,
     mut mine_exploded_event_writer: EventWriter<MineExplodedEvent>,
+    mut tile_uncovered_event_writer: EventWriter<TileUncoveredEvent>,
 ) {
-    for (entity, parent) in children.iter() {
-        // Remove tile cover
-        commands.entity(entity).despawn_recursive();
-
-        let (&coordinates, mine, neighbor) = match parents.get(parent.0) {
-            Ok(parent) => parent,
-            Err(error) => {
-                log::error!("{}", error);
-                continue;
-            }
-        };
-
-        match board.uncover_tile(coordinates) {
-            None => log::debug!(
-                "Tried to uncover an already uncovered tile at {}",
+    // Mines are placed around the first uncovered tile before it is uncovered
+    if board.deferred_placement.is_some() {
+        return;
+    }
+    let uncovering = std::mem::take(&mut board.uncovering);
+    for (coordinates, id) in uncovering {
+        let mark = board.mark_at(coordinates);
+        if !board.uncover_tile(coordinates) {
+            log::debug!(
+                "Tried to uncover an already uncovered or flagged tile at {}",
                 coordinates
-            ),
-            Some(_tile) => log::debug!("Uncovered tile at {} (entity: {:?})", coordinates, entity),
+            );
+            continue;
         }
-
-        if board.is_completed() {
-            log::info!("Board completed!");
-            board_completed_event_writer.send(BoardCompletedEvent);
+        log::debug!("Uncovered tile at {}", coordinates);
+        // Remove tile cover
+        if let Some(cover) = board.covers.remove(&coordinates) {
+            commands.entity(cover).despawn_recursive();
+        }
+        // Tiles uncovered by propagation belong to the move that started it, even if other moves were made since
+        let mut board_move = history
+            .as_mut()
+            .zip(id)
+            .and_then(|(history, id)| history.get_mut(id));
+        if let Some(board_move) = board_move.as_mut() {
+            board_move.uncovered.push((coordinates, mark));
         }
+        let tile = match board.tile_map.get(coordinates) {
+            Some(&tile) => tile,
+            None => continue,
+        };
+        tile_uncovered_event_writer.send(TileUncoveredEvent { coordinates, tile });
 
-        if mine.is_some() {
-            log::info!("Boom!");
-            mine_exploded_event_writer.send(MineExplodedEvent);
-        } else if neighbor.is_none() {
-            // Propagate event
-            for entity in board.get_covered_neighbors(coordinates) {
-                commands.entity(entity).insert(Uncovered);
+        match tile {
+            Tile::Mine if board.is_playing() => {
+                log::info!("Boom!");
+                board.status = BoardStatus::Lost;
+                board.exploded = Some(coordinates);
+                if let Some(board_move) = board_move {
+                    board_move.exploded = Some(coordinates);
+                }
+                mine_exploded_event_writer.send(MineExplodedEvent {
+                    coordinates,
+                    elapsed: stats.elapsed,
+                    clicks: stats.clicks(),
+                    seed: board.seed,
+                });
             }
-        };
+            Tile::Empty => {
+                // Propagate event
+                let neighbors = board.get_covered_neighbors(coordinates);
+                board
+                    .uncovering
+                    .extend(neighbors.into_iter().map(|coordinates| (coordinates, id)));
+            }
+            _ => (),
+        }
+    }
+
+    // Checked once all tiles of this frame are uncovered, so an explosion always takes precedence
+    if board.is_playing() && board.is_completed() {
+        log::info!("Board completed!");
+        board.status = BoardStatus::Won;
+        board_completed_event_writer.send(BoardCompletedEvent {
+            elapsed: stats.elapsed,
+            clicks: stats.clicks(),
+            seed: board.seed,
+        });
     }
 }
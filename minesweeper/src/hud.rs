@@ -0,0 +1,284 @@
+use crate::AppState;
+use bevy::log;
+use bevy::prelude::*;
+use board_plugin::events::{
+    BoardCompletedEvent, BoardGeneratedEvent, ExplosionUndoneEvent, MineExplodedEvent,
+    TileFlaggedEvent, TimerTickedEvent,
+};
+use board_plugin::resources::{Board, BoardOptions, TouchOptions};
+
+const HUD_HEIGHT: f32 = 50.;
+const FONT_SIZE: f32 = 24.;
+const FLAG_MODE_COLORS: [Color; 2] = [Color::GRAY, Color::ORANGE_RED];
+
+/// Heads-up display with the remaining mine counter, the game timer, a reset button
+/// and a flag mode button for touch screens
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_hud)
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(reset_hud))
+            .add_system(update_mine_counter)
+            .add_system(update_timer)
+            .add_system(update_face)
+            .add_system(reset_button)
+            .add_system(flag_mode_button);
+    }
+}
+
+/// HUD text element
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Component)]
+enum HudText {
+    MineCounter,
+    Timer,
+    Face,
+}
+
+#[derive(Debug, Component)]
+struct ResetButton;
+
+/// Toggles the touch flag mode, making taps mark tiles
+#[derive(Debug, Component)]
+struct FlagModeButton;
+
+/// Reset button face, reflecting the state of the game
+#[derive(Debug, Copy, Clone)]
+enum Face {
+    Playing,
+    Won,
+    Lost,
+}
+
+impl Face {
+    fn text(&self) -> &'static str {
+        match self {
+            Self::Playing => ":)",
+            Self::Won => "B)",
+            Self::Lost => "X(",
+        }
+    }
+}
+
+fn setup_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn_bundle(UiCameraBundle::default());
+
+    let font = asset_server.load("fonts/pixeled.ttf");
+    let text = |value: &str, color: Color| TextBundle {
+        text: Text::with_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size: FONT_SIZE,
+                color,
+            },
+            Default::default(),
+        ),
+        ..Default::default()
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Px(HUD_HEIGHT)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(0.),
+                    left: Val::Px(0.),
+                    ..Default::default()
+                },
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                padding: Rect::all(Val::Px(10.)),
+                ..Default::default()
+            },
+            color: Color::rgb(0.15, 0.15, 0.15).into(),
+            ..Default::default()
+        })
+        .insert(Name::new("HUD"))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(text(&format_counter(0), Color::RED))
+                        .insert(HudText::MineCounter);
+                    parent
+                        .spawn_bundle(ButtonBundle {
+                            style: Style {
+                                size: Size::new(
+                                    Val::Px(HUD_HEIGHT - 10.),
+                                    Val::Px(HUD_HEIGHT - 10.),
+                                ),
+                                margin: Rect {
+                                    left: Val::Px(10.),
+                                    ..Default::default()
+                                },
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            color: FLAG_MODE_COLORS[0].into(),
+                            ..Default::default()
+                        })
+                        .insert(FlagModeButton)
+                        .with_children(|parent| {
+                            parent.spawn_bundle(text("F", Color::YELLOW));
+                        });
+                });
+            parent
+                .spawn_bundle(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(HUD_HEIGHT * 1.5), Val::Px(HUD_HEIGHT - 10.)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::GRAY.into(),
+                    ..Default::default()
+                })
+                .insert(ResetButton)
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(text(Face::Playing.text(), Color::YELLOW))
+                        .insert(HudText::Face);
+                });
+            parent
+                .spawn_bundle(text(&format_counter(0), Color::RED))
+                .insert(HudText::Timer);
+        });
+}
+
+/// Three digit counter display
+fn format_counter(value: i64) -> String {
+    if value < 0 {
+        format!("-{:02}", (-value).min(99))
+    } else {
+        format!("{:03}", value.min(999))
+    }
+}
+
+fn set_text(text: &mut Text, value: &str) {
+    if let Some(section) = text.sections.first_mut() {
+        section.value = value.to_string();
+    }
+}
+
+fn reset_hud(board_options: Option<Res<BoardOptions>>, mut query: Query<(&mut Text, &HudText)>) {
+    let mine_count = board_options.map_or(0, |options| options.placeable_mine_count());
+    for (mut text, hud_text) in query.iter_mut() {
+        let value = match hud_text {
+            HudText::MineCounter => format_counter(mine_count as i64),
+            HudText::Timer => format_counter(0),
+            HudText::Face => Face::Playing.text().to_string(),
+        };
+        set_text(&mut text, &value);
+    }
+}
+
+fn update_mine_counter(
+    board: Option<Res<Board>>,
+    mut tile_flagged_event_reader: EventReader<TileFlaggedEvent>,
+    mut board_generated_event_reader: EventReader<BoardGeneratedEvent>,
+    mut query: Query<(&mut Text, &HudText)>,
+) {
+    let flagged = tile_flagged_event_reader.iter().count() > 0;
+    let generated = board_generated_event_reader.iter().count() > 0;
+    let board = match board {
+        Some(board) if flagged || generated => board,
+        _ => return,
+    };
+    let value = format_counter(board.remaining_mines() as i64);
+    for (mut text, _) in query
+        .iter_mut()
+        .filter(|(_, hud_text)| **hud_text == HudText::MineCounter)
+    {
+        set_text(&mut text, &value);
+    }
+}
+
+fn update_timer(
+    mut timer_ticked_event_reader: EventReader<TimerTickedEvent>,
+    mut query: Query<(&mut Text, &HudText)>,
+) {
+    let elapsed = match timer_ticked_event_reader.iter().last() {
+        Some(event) => event.elapsed,
+        None => return,
+    };
+    let value = format_counter(elapsed.as_secs() as i64);
+    for (mut text, _) in query
+        .iter_mut()
+        .filter(|(_, hud_text)| **hud_text == HudText::Timer)
+    {
+        set_text(&mut text, &value);
+    }
+}
+
+fn update_face(
+    mut board_completed_event_reader: EventReader<BoardCompletedEvent>,
+    mut mine_exploded_event_reader: EventReader<MineExplodedEvent>,
+    mut explosion_undone_event_reader: EventReader<ExplosionUndoneEvent>,
+    mut query: Query<(&mut Text, &HudText)>,
+) {
+    let lost = mine_exploded_event_reader.iter().count() > 0;
+    let won = board_completed_event_reader.iter().count() > 0;
+    let undone = explosion_undone_event_reader.iter().count() > 0;
+    let face = if lost {
+        Face::Lost
+    } else if won {
+        Face::Won
+    } else if undone {
+        Face::Playing
+    } else {
+        return;
+    };
+    for (mut text, _) in query
+        .iter_mut()
+        .filter(|(_, hud_text)| **hud_text == HudText::Face)
+    {
+        set_text(&mut text, face.text());
+    }
+}
+
+fn reset_button(
+    mut state: ResMut<State<AppState>>,
+    query: Query<&Interaction, (Changed<Interaction>, With<ResetButton>)>,
+) {
+    if !query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Clicked)
+    {
+        return;
+    }
+    if state.current() != &AppState::Paused {
+        log::info!("restarting game from HUD");
+        state
+            .set(AppState::Restarting)
+            .unwrap_or_else(|error| panic!("Failed to initiate game restart: {}", error))
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn flag_mode_button(
+    mut touch_options: ResMut<TouchOptions>,
+    mut query: Query<(&Interaction, &mut UiColor), (Changed<Interaction>, With<FlagModeButton>)>,
+) {
+    for (interaction, mut color) in query.iter_mut() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        touch_options.flag_mode = !touch_options.flag_mode;
+        log::info!(
+            "flag mode {}",
+            if touch_options.flag_mode { "on" } else { "off" }
+        );
+        *color = FLAG_MODE_COLORS[touch_options.flag_mode as usize].into();
+    }
+}
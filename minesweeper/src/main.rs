@@ -1,12 +1,27 @@
+mod hud;
+#[cfg(not(target_arch = "wasm32"))]
+mod persistence;
+#[cfg(not(target_arch = "wasm32"))]
+mod replay;
+
 use bevy::log;
 use bevy::prelude::*;
 use board_plugin::assets::{board::BoardAssets, SpriteMaterial};
-use board_plugin::resources::BoardOptions;
+use board_plugin::components::BoardCamera;
+use board_plugin::resources::{
+    BoardOptions, BoardPosition, BoardSource, Generator, InputAction, InputMap, SafeArea, TileSize,
+    UndoMode,
+};
 use board_plugin::BoardPlugin;
+use hud::HudPlugin;
 
 #[cfg(feature = "debug")]
 use bevy_inspector_egui::WorldInspectorPlugin;
 
+/// Input bindings, read on start when present
+#[cfg(not(target_arch = "wasm32"))]
+const INPUT_MAP_PATH: &str = "input_map.ron";
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum AppState {
     InGame,
@@ -34,15 +49,47 @@ fn main() {
         .add_plugin(BoardPlugin::<AppState> {
             current_state: AppState::InGame,
         })
+        .add_plugin(HudPlugin)
         .add_system(state_handler)
         .add_startup_system(setup_board);
 
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        app.add_startup_system(load_input_map);
+        let replay = replay::ReplayPlugin::from_args();
+        // Pass `--no-save` to start a new game without touching the save file.
+        // Recorded and replayed games always start from a new board, so they are not saved either.
+        if replay.is_none() && !std::env::args().any(|arg| arg == "--no-save") {
+            app.add_plugin(persistence::PersistencePlugin);
+        }
+        if let Some(replay) = replay {
+            app.add_plugin(replay);
+        }
+    }
+
     app.run();
 }
 
+/// Reads the input bindings from `INPUT_MAP_PATH`, the board plugin keeps the default bindings otherwise
+#[cfg(not(target_arch = "wasm32"))]
+fn load_input_map(mut commands: Commands) {
+    if !std::path::Path::new(INPUT_MAP_PATH).exists() {
+        return;
+    }
+    match InputMap::load(INPUT_MAP_PATH) {
+        Ok(input_map) => {
+            log::info!("loaded input bindings from {}", INPUT_MAP_PATH);
+            commands.insert_resource(input_map);
+        }
+        Err(error) => log::error!("{}", error),
+    }
+}
+
 fn camera_setup(mut commands: Commands) {
     // 2D orthographic camera
-    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+    commands
+        .spawn_bundle(OrthographicCameraBundle::new_2d())
+        .insert(BoardCamera);
 }
 
 fn setup_board(
@@ -50,11 +97,32 @@ fn setup_board(
     mut state: ResMut<State<AppState>>,
     asset_server: Res<AssetServer>,
 ) {
+    // Pass `--puzzle <path>` to play a puzzle from the assets folder, e.g. `puzzles/corner.puzzle`.
+    // The board is rebuilt whenever the puzzle file is saved.
+    let args: Vec<String> = std::env::args().collect();
+    let source = match args.iter().position(|arg| arg == "--puzzle") {
+        Some(index) if index + 1 < args.len() => {
+            if let Err(error) = asset_server.watch_for_changes() {
+                log::warn!("Failed to watch puzzle changes: {:?}", error);
+            }
+            BoardSource::Puzzle(asset_server.load(args[index + 1].as_str()))
+        }
+        _ => BoardSource::Generated,
+    };
     commands.insert_resource(BoardOptions {
         map_size: (20, 20),
         mine_count: 40,
         tile_padding: 3.,
-        safe_start: true,
+        // Leave room for the HUD at the top of the window
+        position: BoardPosition::Centered {
+            offset: Vec3::new(0., -25., 0.),
+        },
+        tile_size: TileSize::Adaptive { min: 10., max: 27. },
+        first_click_safety: Some(SafeArea::Square),
+        generator: Generator::NoGuess { max_attempts: 500 },
+        question_marks: true,
+        undo: UndoMode::Assisted,
+        source,
         ..Default::default()
     });
     commands.insert_resource(BoardAssets {
@@ -79,6 +147,22 @@ fn setup_board(
             color: Color::WHITE,
             texture: asset_server.load("sprites/flag.png"),
         },
+        question_material: SpriteMaterial {
+            color: Color::WHITE,
+            texture: asset_server.load("sprites/question.png"),
+        },
+        exploded_mine_material: SpriteMaterial {
+            color: Color::RED,
+            ..Default::default()
+        },
+        wrong_flag_material: SpriteMaterial {
+            color: Color::RED,
+            texture: asset_server.load("sprites/flag.png"),
+        },
+        cursor_material: SpriteMaterial {
+            color: Color::rgba(1., 1., 0., 0.4),
+            ..Default::default()
+        },
         neighbor_font: asset_server.load("fonts/pixeled.ttf"),
         mine_counter_colors: BoardAssets::default_colors(),
     });
@@ -87,8 +171,16 @@ fn setup_board(
         panic!("Failed to initialize game: {}", err);
     });
 }
-fn state_handler(mut state: ResMut<State<AppState>>, keys: Res<Input<KeyCode>>) {
-    if keys.just_pressed(KeyCode::C) {
+fn state_handler(
+    mut state: ResMut<State<AppState>>,
+    input_map: Res<InputMap>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+) {
+    let just_pressed =
+        |action| input_map.just_pressed(action, &keys, &mouse_buttons, &gamepad_buttons);
+    if just_pressed(InputAction::Clear) {
         log::debug!("clearing detected");
         if state.current() == &AppState::InGame {
             log::info!("clearing game");
@@ -98,7 +190,7 @@ fn state_handler(mut state: ResMut<State<AppState>>, keys: Res<Input<KeyCode>>)
         }
     }
 
-    if keys.just_pressed(KeyCode::G) {
+    if just_pressed(InputAction::Restart) {
         log::debug!("loading detected");
         if state.current() != &AppState::Paused {
             log::info!("loading game");
@@ -113,7 +205,7 @@ fn state_handler(mut state: ResMut<State<AppState>>, keys: Res<Input<KeyCode>>)
             .unwrap_or_else(|error| panic!("Failed to finish game restart: {}", error));
     }
 
-    if keys.just_pressed(KeyCode::Escape) {
+    if just_pressed(InputAction::Pause) {
         log::debug!("pause detected");
 
         if state.current() == &AppState::Paused {
@@ -0,0 +1,77 @@
+use bevy::app::AppExit;
+use bevy::log;
+use bevy::prelude::*;
+use board_plugin::events::{TileCoveredEvent, TileFlaggedEvent, TileUncoveredEvent};
+use board_plugin::resources::{Board, BoardSnapshot, BoardStats};
+use std::path::Path;
+
+const SAVE_PATH: &str = "minesweeper_save.ron";
+/// Seconds between a move and the save that includes it, so a crash loses little progress
+const SAVE_DELAY: f64 = 5.;
+
+/// Saves the game in progress shortly after every move and when the app exits,
+/// and resumes it on the next start
+pub struct PersistencePlugin;
+
+impl Plugin for PersistencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(resume_game)
+            .add_system_to_stage(CoreStage::Last, save_game);
+    }
+}
+
+fn resume_game(mut commands: Commands) {
+    if !Path::new(SAVE_PATH).exists() {
+        return;
+    }
+    match BoardSnapshot::load(SAVE_PATH) {
+        Ok(snapshot) => {
+            log::info!("resuming game from {}", SAVE_PATH);
+            commands.insert_resource(snapshot);
+        }
+        Err(error) => log::error!("{}", error),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_game(
+    time: Res<Time>,
+    board: Option<Res<Board>>,
+    stats: Option<Res<BoardStats>>,
+    mut unsaved_since: Local<Option<f64>>,
+    mut app_exit_event_reader: EventReader<AppExit>,
+    mut tile_uncovered_event_reader: EventReader<TileUncoveredEvent>,
+    mut tile_flagged_event_reader: EventReader<TileFlaggedEvent>,
+    mut tile_covered_event_reader: EventReader<TileCoveredEvent>,
+) {
+    let moves = tile_uncovered_event_reader.iter().count()
+        + tile_flagged_event_reader.iter().count()
+        + tile_covered_event_reader.iter().count();
+    if moves > 0 && unsaved_since.is_none() {
+        *unsaved_since = Some(time.seconds_since_startup());
+    }
+    let exiting = app_exit_event_reader.iter().count() > 0;
+    let save_due = unsaved_since.map_or(false, |since| {
+        time.seconds_since_startup() - since >= SAVE_DELAY
+    });
+    if !exiting && !save_due {
+        return;
+    }
+    *unsaved_since = None;
+    match (board, stats) {
+        (Some(board), Some(stats)) if board.is_playing() && board.is_started() => {
+            log::info!("saving game to {}", SAVE_PATH);
+            if let Err(error) = BoardSnapshot::new(&board, &stats).save(SAVE_PATH) {
+                log::error!("{}", error);
+            }
+        }
+        // Finished or untouched games are not worth resuming
+        _ => {
+            if Path::new(SAVE_PATH).exists() {
+                if let Err(error) = std::fs::remove_file(SAVE_PATH) {
+                    log::error!("Failed to remove {}: {}", SAVE_PATH, error);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use bevy::app::AppExit;
+use bevy::log;
+use bevy::prelude::*;
+use board_plugin::resources::{
+    InputAction, InputMap, PlaybackSpeed, Replay, ReplayPlayer, ReplayRecorder,
+};
+
+/// Records the games to a replay file, or plays a replay file back
+pub enum ReplayPlugin {
+    /// Writes the replay of the last game to the given path on exit
+    Record(String),
+    /// Plays the replay at the given path back. `InputAction::StepReplay` plays the next action in step mode.
+    Play(String, PlaybackSpeed),
+}
+
+impl ReplayPlugin {
+    /// Reads the `--record <path>` or `--replay <path> [--speed <factor|step>]` arguments
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        let value_of = |name: &str| {
+            args.iter()
+                .position(|arg| arg == name)
+                .and_then(|index| args.get(index + 1))
+                .cloned()
+        };
+        if let Some(path) = value_of("--record") {
+            return Some(Self::Record(path));
+        }
+        let path = value_of("--replay")?;
+        let speed = match value_of("--speed") {
+            None => PlaybackSpeed::RealTime,
+            Some(speed) if speed == "step" => PlaybackSpeed::Step,
+            Some(speed) => match speed.parse() {
+                Ok(factor) => PlaybackSpeed::FastForward(factor),
+                Err(_) => {
+                    log::warn!("Invalid replay speed {}, playing at real time", speed);
+                    PlaybackSpeed::RealTime
+                }
+            },
+        };
+        Some(Self::Play(path, speed))
+    }
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        match self {
+            Self::Record(path) => {
+                let path = path.clone();
+                app.insert_resource(ReplayRecorder::default())
+                    .add_system_to_stage(
+                    CoreStage::Last,
+                    move |recorder: Res<ReplayRecorder>,
+                          mut app_exit_event_reader: EventReader<AppExit>| {
+                        save_replay(&path, &recorder, &mut app_exit_event_reader)
+                    },
+                );
+            }
+            Self::Play(path, speed) => match Replay::load(path) {
+                Ok(replay) => {
+                    log::info!("playing replay {} back", path);
+                    // Overrides the options inserted by the game setup
+                    let options = replay.board_options();
+                    app.insert_resource(ReplayPlayer::new(replay, *speed))
+                        .add_startup_system_to_stage(
+                            StartupStage::PostStartup,
+                            move |mut commands: Commands| commands.insert_resource(options.clone()),
+                        )
+                        .add_system(step_replay);
+                }
+                Err(error) => log::error!("{}", error),
+            },
+        }
+    }
+}
+
+fn save_replay(
+    path: &str,
+    recorder: &ReplayRecorder,
+    app_exit_event_reader: &mut EventReader<AppExit>,
+) {
+    if app_exit_event_reader.iter().count() == 0 {
+        return;
+    }
+    if let Some(replay) = recorder.replay() {
+        log::info!("saving replay to {}", path);
+        if let Err(error) = replay.save(path) {
+            log::error!("{}", error);
+        }
+    }
+}
+
+fn step_replay(
+    mut player: ResMut<ReplayPlayer>,
+    input_map: Res<InputMap>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+) {
+    if input_map.just_pressed(
+        InputAction::StepReplay,
+        &keys,
+        &mouse_buttons,
+        &gamepad_buttons,
+    ) {
+        player.step();
+    }
+}